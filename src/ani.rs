@@ -0,0 +1,130 @@
+use crate::{
+    Chunk, List, Riff, RiffParser, RiffResult, RiffType,
+    fourcc::Fourcc,
+    riff::{missing_error, validate_tag},
+};
+use alloc::vec::Vec;
+use binrw::{
+    BinRead,
+    io::{Read, Seek},
+};
+use core::fmt::Debug;
+
+pub mod tag {
+    use super::Fourcc;
+    pub const ACON: Fourcc = Fourcc::new(*b"ACON");
+    pub const ANIH: Fourcc = Fourcc::new(*b"anih");
+    pub const RATE: Fourcc = Fourcc::new(*b"rate");
+    pub const SEQ: Fourcc = Fourcc::new(*b"seq ");
+    pub const FRAM: Fourcc = Fourcc::new(*b"fram");
+    pub const ICON: Fourcc = Fourcc::new(*b"icon");
+}
+
+/// https://www.gdgsoft.com/anituner/help/aniformat.htm
+#[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct AniHeader {
+    pub size: u32,
+    pub frames: u32,
+    pub steps: u32,
+    pub width: u32,
+    pub height: u32,
+    pub bit_count: u32,
+    pub planes: u32,
+    pub display_rate: u32,
+    pub flags: u32,
+}
+
+impl AniHeader {
+    /// Frames are ICO/CUR data rather than raw bitmaps
+    pub const FLAG_ICON: u32 = 0x1;
+    /// The file contains a `seq ` chunk
+    pub const FLAG_SEQUENCE: u32 = 0x2;
+}
+
+pub struct AniParser<R> {
+    parser: RiffParser<R>,
+    pub ani_header: AniHeader,
+    /// Per step display rate in jiffies (1/60s), overriding `display_rate`
+    pub rates: Option<Vec<u32>>,
+    /// Frame index to display at each step
+    pub sequence: Option<Vec<u32>>,
+    pub fram: Riff<List>,
+    pub frames: Vec<Riff<Chunk>>,
+}
+
+impl<R: Read + Seek> AniParser<R> {
+    pub fn new(parser: RiffParser<R>) -> RiffResult<Self> {
+        let riff = parser.riff()?;
+        validate_tag(&riff, tag::ACON)?;
+
+        let mut ani_header = None;
+        let mut rates = None;
+        let mut sequence = None;
+        let mut fram = None;
+        for result in parser.chunks(riff) {
+            match result? {
+                RiffType::Chunk(chunk) => match chunk.id() {
+                    tag::ANIH => ani_header = Some(parser.read_data_struct::<AniHeader>(chunk)?),
+                    tag::RATE => rates = Some(Self::read_u32_array(&parser, chunk)?),
+                    tag::SEQ => sequence = Some(Self::read_u32_array(&parser, chunk)?),
+                    _ => {}
+                },
+                RiffType::List(list) if list.id() == tag::FRAM => fram = Some(list),
+                RiffType::List(_) => {}
+            }
+        }
+        let ani_header = ani_header.ok_or_else(|| missing_error(riff.position(), tag::ANIH))?;
+        let fram = fram.ok_or_else(|| missing_error(riff.position(), tag::FRAM))?;
+
+        let mut frames = Vec::with_capacity(ani_header.frames as usize);
+        for result in parser.chunks(fram) {
+            if let RiffType::Chunk(icon) = result?
+                && icon.id() == tag::ICON
+            {
+                frames.push(icon);
+            }
+        }
+
+        Ok(Self {
+            parser,
+            ani_header,
+            rates,
+            sequence,
+            fram,
+            frames,
+        })
+    }
+
+    fn read_u32_array(parser: &RiffParser<R>, chunk: Riff<Chunk>) -> RiffResult<Vec<u32>> {
+        Ok(parser
+            .read_data_vec(chunk)?
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect())
+    }
+
+    /// Read the ICO/CUR data of frame `index`
+    pub fn frame_data(&self, index: usize) -> RiffResult<Vec<u8>> {
+        let frame = self
+            .frames
+            .get(index)
+            .ok_or_else(|| missing_error(self.fram.position(), tag::ICON))?;
+        self.parser.read_data_vec(*frame)
+    }
+
+    pub fn riff_parser(&self) -> &RiffParser<R> {
+        &self.parser
+    }
+}
+
+impl<R: Read + Seek> Debug for AniParser<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AniParser")
+            .field("ani_header", &self.ani_header)
+            .field("rates", &self.rates)
+            .field("sequence", &self.sequence)
+            .finish()
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    Chunk, List, Riff, RiffError, RiffResult, RiffType,
+    fourcc::{Fourcc, tag},
+    riff::{Header, HeaderType, header_io_error},
+};
+use alloc::{sync::Arc, vec, vec::Vec};
+use binrw::{BinRead, Endian, io::Cursor};
+use core::{
+    mem::size_of,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tokio::{
+    io::{self, AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, SeekFrom},
+    sync::Mutex,
+};
+
+/// Async counterpart of `RiffParser` for tokio readers.
+/// Mirrors the sync API, every read locks the shared reader for its duration.
+pub struct AsyncRiffParser<R> {
+    reader: Arc<Mutex<R>>,
+    /// Set for `RIFX` files when the `RIFF` header is read
+    big_endian: AtomicBool,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncRiffParser<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: Arc::new(Mutex::new(reader)),
+            big_endian: AtomicBool::new(false),
+        }
+    }
+
+    pub async fn riff(&self) -> RiffResult<Riff<List>> {
+        let mut reader = self.reader.lock().await;
+        let mut magic = [0u8; 4];
+        reader
+            .read_exact(&mut magic)
+            .await
+            .map_err(|err| header_io_error(err, RiffError::NotRiff))?;
+        let endian = match Fourcc::new(magic) {
+            tag::RIFF => Endian::Little,
+            tag::RIFX => Endian::Big,
+            _ => return Err(RiffError::NotRiff),
+        };
+        reader.seek(SeekFrom::Current(-4)).await?;
+        let (HeaderType::Riff(header) | HeaderType::Rifx(header)) =
+            read_header(&mut *reader, endian)
+                .await
+                .map_err(|err| header_io_error(err, RiffError::NotRiff))?
+        else {
+            return Err(RiffError::NotRiff);
+        };
+        self.big_endian
+            .store(endian == Endian::Big, Ordering::Relaxed);
+        let data_start = reader.stream_position().await?;
+        Ok(Riff::new(header, data_start))
+    }
+
+    /// Byte order of the file, little endian unless `riff` read a `RIFX` header
+    pub fn endian(&self) -> Endian {
+        if self.big_endian.load(Ordering::Relaxed) {
+            Endian::Big
+        } else {
+            Endian::Little
+        }
+    }
+
+    pub fn chunks(&self, chunk: Riff<List>) -> AsyncListIter<R> {
+        AsyncListIter {
+            reader: Arc::clone(&self.reader),
+            next_position: chunk.position(),
+            list: chunk,
+            endian: self.endian(),
+        }
+    }
+
+    /// Types without a fixed byte order are read in the byte order of the file
+    pub async fn read_data_struct<S>(&self, chunk: Riff<Chunk>) -> RiffResult<S>
+    where
+        S: BinRead + Sized,
+        for<'a> <S as BinRead>::Args<'a>: Default,
+    {
+        let data = self.read_data_vec(chunk).await?;
+        Ok(S::read_options(
+            &mut Cursor::new(data),
+            self.endian(),
+            Default::default(),
+        )?)
+    }
+
+    pub async fn read_data_vec<H: Header>(&self, chunk: Riff<H>) -> RiffResult<Vec<u8>> {
+        let mut buffer = vec![0u8; chunk.data_size() as usize];
+        self.read_data(chunk, &mut buffer).await?;
+        Ok(buffer)
+    }
+
+    pub async fn read_data<H: Header>(&self, chunk: Riff<H>, buffer: &mut [u8]) -> RiffResult<()> {
+        if buffer.len() > chunk.data_size() as usize {
+            return Err(RiffError::MalformedChunk {
+                position: chunk.position(),
+                message: "buffer too large".into(),
+            });
+        }
+        let mut reader = self.reader.lock().await;
+        reader.seek(SeekFrom::Start(chunk.position())).await?;
+        reader.read_exact(buffer).await?;
+        Ok(())
+    }
+}
+
+/// Async counterpart of `ListIter`, call `next_chunk` until it returns `None`
+pub struct AsyncListIter<R> {
+    reader: Arc<Mutex<R>>,
+    list: Riff<List>,
+    next_position: u64,
+    endian: Endian,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncListIter<R> {
+    /// Byte offset of the header of the next item this iterator will return
+    pub fn position(&self) -> u64 {
+        self.next_position
+    }
+
+    pub async fn next_chunk(&mut self) -> Option<RiffResult<RiffType>> {
+        if self.next_position >= self.list.end_position() - size_of::<Fourcc>() as u64 {
+            None
+        } else {
+            Some(self.read_next().await)
+        }
+    }
+
+    async fn read_next(&mut self) -> RiffResult<RiffType> {
+        let mut reader = self.reader.lock().await;
+        reader.seek(SeekFrom::Start(self.next_position)).await?;
+        let header = read_header(&mut *reader, self.endian)
+            .await
+            .map_err(|err| {
+                header_io_error(
+                    err,
+                    RiffError::MalformedChunk {
+                        position: self.next_position,
+                        message: "truncated chunk header".into(),
+                    },
+                )
+            })?;
+        let data_start = reader.stream_position().await?;
+        let item = match header {
+            HeaderType::List(list_header) => RiffType::List(Riff::new(list_header, data_start)),
+            HeaderType::Chunk(chunk_header) => RiffType::Chunk(Riff::new(chunk_header, data_start)),
+            HeaderType::Riff(_) | HeaderType::Rifx(_) => {
+                return Err(RiffError::MalformedChunk {
+                    position: data_start,
+                    message: "nested RIFF header".into(),
+                });
+            }
+        };
+        self.next_position = match item {
+            RiffType::List(list) => list.end_position(),
+            RiffType::Chunk(chunk) => chunk.end_position(),
+        };
+        Ok(item)
+    }
+}
+
+async fn read_header<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    endian: Endian,
+) -> io::Result<HeaderType> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header).await?;
+    let id = Fourcc::new([header[0], header[1], header[2], header[3]]);
+    let size = [header[4], header[5], header[6], header[7]];
+    let size = match endian {
+        Endian::Big => u32::from_be_bytes(size),
+        Endian::Little => u32::from_le_bytes(size),
+    };
+    if id == tag::RIFF || id == tag::RIFX || id == tag::LIST {
+        let mut list_id = [0u8; 4];
+        reader.read_exact(&mut list_id).await?;
+        let list = List::new(size, Fourcc::new(list_id));
+        Ok(match id {
+            tag::RIFF => HeaderType::Riff(list),
+            tag::RIFX => HeaderType::Rifx(list),
+            _ => HeaderType::List(list),
+        })
+    } else {
+        Ok(HeaderType::Chunk(Chunk::new(id, size)))
+    }
+}
@@ -1,27 +1,49 @@
-use crate::{Chunk, List, Riff, RiffParser, RiffType, fourcc::Fourcc, riff::Header};
-use alloc::{format, vec::Vec};
+use crate::{
+    Chunk, HeaderType, List, ListIter, Riff, RiffError, RiffParser, RiffResult, RiffType,
+    fourcc::{Fourcc, FourccMap},
+    info::InfoList,
+    riff::{eof_error, missing_error, next_chunk, next_list, validate_tag},
+    wav::WavWriter,
+};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
 use binrw::{
-    BinRead, Error,
-    io::{self, Read, Seek},
+    BinRead, BinWrite, Endian, Error as BinError, binread,
+    io::{Read, Seek, SeekFrom, Write},
+};
+use core::{
+    convert::TryFrom,
+    fmt::{Debug, Display, Formatter},
+    mem::size_of,
 };
-use core::{convert::TryFrom, fmt::Debug};
 
 pub mod tag {
     use super::Fourcc;
     pub const AVI: Fourcc = Fourcc::new(*b"AVI ");
+    pub const AVIX: Fourcc = Fourcc::new(*b"AVIX");
     pub const HDRL: Fourcc = Fourcc::new(*b"hdrl");
     pub const AVIH: Fourcc = Fourcc::new(*b"avih");
     pub const STRL: Fourcc = Fourcc::new(*b"strl");
     pub const STRH: Fourcc = Fourcc::new(*b"strh");
     pub const STRF: Fourcc = Fourcc::new(*b"strf");
+    pub const STRD: Fourcc = Fourcc::new(*b"strd");
+    pub const STRN: Fourcc = Fourcc::new(*b"strn");
+    pub const VPRP: Fourcc = Fourcc::new(*b"vprp");
     pub const VIDS: Fourcc = Fourcc::new(*b"vids");
     pub const AUDS: Fourcc = Fourcc::new(*b"auds");
+    pub const TXTS: Fourcc = Fourcc::new(*b"txts");
+    pub const MIDS: Fourcc = Fourcc::new(*b"mids");
     pub const MOVI: Fourcc = Fourcc::new(*b"movi");
+    pub const REC: Fourcc = Fourcc::new(*b"rec ");
+    pub const ODML: Fourcc = Fourcc::new(*b"odml");
+    pub const JUNK: Fourcc = Fourcc::new(*b"JUNK");
+    pub const IDX1: Fourcc = Fourcc::new(*b"idx1");
+    pub const DMLH: Fourcc = Fourcc::new(*b"dmlh");
 
     pub const DATA_VIDEO_COMPRESSED: [u8; 2] = *b"dc";
     pub const DATA_VIDEO_UNCOMPRESSED: [u8; 2] = *b"db";
     pub const DATA_PALETTE_CHANGED: [u8; 2] = *b"pc";
     pub const DATA_AUDIO: [u8; 2] = *b"wb";
+    pub const DATA_TEXT: [u8; 2] = *b"tx";
 
     pub const fn stream(mut stream_index: u32, datatype: [u8; 2]) -> Fourcc {
         if stream_index > 99 {
@@ -38,6 +60,7 @@ pub mod tag {
 
 /// https://learn.microsoft.com/en-us/previous-versions/ms779632(v=vs.85)
 #[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[br(little)]
 pub struct AviMainHeader {
     pub micro_sec_per_frame: u32,
@@ -53,8 +76,229 @@ pub struct AviMainHeader {
     pub reserved: [u32; 4],
 }
 
+impl AviMainHeader {
+    pub const FLAG_HAS_INDEX: u32 = 0x10;
+    pub const FLAG_MUST_USE_INDEX: u32 = 0x20;
+    pub const FLAG_IS_INTERLEAVED: u32 = 0x100;
+    pub const FLAG_TRUST_CK_TYPE: u32 = 0x800;
+    pub const FLAG_WAS_CAPTURE_FILE: u32 = 0x10000;
+    pub const FLAG_COPYRIGHTED: u32 = 0x20000;
+
+    pub fn has_index(&self) -> bool {
+        self.flags & Self::FLAG_HAS_INDEX != 0
+    }
+
+    /// Chunk order must be taken from `idx1` rather than the order in `movi`
+    pub fn must_use_index(&self) -> bool {
+        self.flags & Self::FLAG_MUST_USE_INDEX != 0
+    }
+
+    pub fn is_interleaved(&self) -> bool {
+        self.flags & Self::FLAG_IS_INTERLEAVED != 0
+    }
+
+    /// The file was preallocated for capturing real time video
+    pub fn is_capture_file(&self) -> bool {
+        self.flags & Self::FLAG_WAS_CAPTURE_FILE != 0
+    }
+
+    pub fn is_copyrighted(&self) -> bool {
+        self.flags & Self::FLAG_COPYRIGHTED != 0
+    }
+
+    #[cfg(feature = "bitflags")]
+    pub fn flags_decoded(&self) -> AviFlags {
+        AviFlags::from_bits_retain(self.flags)
+    }
+}
+
+#[cfg(feature = "bitflags")]
+bitflags::bitflags! {
+    /// `AviMainHeader::flags`. There's no variable bitrate flag, `vfw.h` doesn't define one.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub struct AviFlags: u32 {
+        const HAS_INDEX = AviMainHeader::FLAG_HAS_INDEX;
+        const MUST_USE_INDEX = AviMainHeader::FLAG_MUST_USE_INDEX;
+        const IS_INTERLEAVED = AviMainHeader::FLAG_IS_INTERLEAVED;
+        const TRUST_CK_TYPE = AviMainHeader::FLAG_TRUST_CK_TYPE;
+        const WAS_CAPTURE_FILE = AviMainHeader::FLAG_WAS_CAPTURE_FILE;
+        const COPYRIGHTED = AviMainHeader::FLAG_COPYRIGHTED;
+    }
+}
+
+impl Display for AviMainHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "Resolution: {}x{}", self.width, self.height)?;
+        if self.micro_sec_per_frame == 0 {
+            writeln!(f, "Frame rate: unknown")?;
+        } else {
+            writeln!(
+                f,
+                "Frame rate: {:.2} fps",
+                1_000_000.0 / self.micro_sec_per_frame as f64
+            )?;
+        }
+        writeln!(f, "Frames: {}", self.total_frames)?;
+        let micros = self.total_frames as u64 * self.micro_sec_per_frame as u64;
+        writeln!(f, "Duration: {}", Hms(micros / 1_000_000))?;
+        writeln!(f, "Streams: {}", self.streams)?;
+        write!(f, "Max bytes/sec: {}", self.max_bytes_per_sec)
+    }
+}
+
+/// Seconds formatted as `HH:MM:SS`
+struct Hms(u64);
+
+impl Display for Hms {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let seconds = self.0;
+        write!(
+            f,
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+}
+
+/// https://learn.microsoft.com/en-us/previous-versions/ms779634(v=vs.85)
+#[derive(BinRead, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct AviIndexEntry {
+    pub chunk_id: Fourcc,
+    pub flags: u32,
+    pub offset: u32,
+    pub size: u32,
+}
+
+impl AviIndexEntry {
+    /// The entry is a `LIST rec ` rather than a chunk
+    pub const FLAG_LIST: u32 = 0x1;
+    pub const FLAG_KEYFRAME: u32 = 0x10;
+    /// The chunk does not affect stream timing, e.g. a palette change
+    pub const FLAG_NO_TIME: u32 = 0x100;
+}
+
+/// The entries of an `idx1` chunk, offsets are relative to the `movi` list id
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AviIndex {
+    pub entries: Vec<AviIndexEntry>,
+}
+
+impl AviIndex {
+    pub fn stream_entries(&self, stream_id: Fourcc) -> impl Iterator<Item = &AviIndexEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.chunk_id == stream_id)
+    }
+
+    /// The `idx1` chunk data for these entries
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * size_of::<AviIndexEntry>());
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.chunk_id.bytes());
+            bytes.extend_from_slice(&entry.flags.to_le_bytes());
+            bytes.extend_from_slice(&entry.offset.to_le_bytes());
+            bytes.extend_from_slice(&entry.size.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// OpenDML AVI File Format Extensions, `dmlh` chunk
+// Ignore DWORD dwFuture[61] array at end
+#[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct OdmlHeader {
+    pub total_frames: u32,
+}
+
+/// OpenDML AVI File Format Extensions, `vprp` chunk
+#[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct VideoProperties {
+    pub video_format_token: u32,
+    pub video_standard: u32,
+    pub vertical_refresh_rate: u32,
+    pub h_total_in_t: u32,
+    pub v_total_in_lines: u32,
+    pub frame_aspect_ratio: FrameAspectRatio,
+    pub frame_width_in_pixels: u32,
+    pub frame_height_in_lines: u32,
+    pub field_per_frame: u32,
+    /// One entry per field, at most two
+    #[br(count = field_per_frame.min(2))]
+    pub field_info: Vec<FieldInfo>,
+}
+
+/// Display aspect ratio `x:y`, e.g. 16:9
+// Packed as a DWORD with x in the high word
+#[derive(BinRead, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct FrameAspectRatio {
+    pub y: u16,
+    pub x: u16,
+}
+
+/// Display aspect ratio `width:height` reduced to lowest terms, see
+/// [`AviParser::display_aspect_ratio`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AspectRatio {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AspectRatio {
+    /// Reduces `width:height`, `None` if either is zero
+    pub fn new(width: u32, height: u32) -> Option<Self> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let (mut a, mut b) = (width, height);
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        Some(Self {
+            width: width / a,
+            height: height / a,
+        })
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+}
+
+impl Display for AspectRatio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:{}", self.width, self.height)
+    }
+}
+
+#[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct FieldInfo {
+    pub compressed_bm_height: u32,
+    pub compressed_bm_width: u32,
+    pub valid_bm_height: u32,
+    pub valid_bm_width: u32,
+    pub valid_bm_x_offset: u32,
+    pub valid_bm_y_offset: u32,
+    pub video_x_offset_in_t: u32,
+    pub video_y_valid_start_line: u32,
+}
+
 /// https://learn.microsoft.com/en-us/previous-versions/ms779638(v=vs.85)
 #[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[br(little)]
 pub struct AviStreamHeader {
     pub fcc_type: Fourcc,
@@ -73,7 +317,32 @@ pub struct AviStreamHeader {
     pub frame: Frame,
 }
 
+impl AviStreamHeader {
+    /// The stream should not be played by default
+    pub const FLAG_DISABLED: u32 = 0x1;
+    /// The video stream contains palette changes
+    pub const FLAG_VIDEO_PALETTE_CHANGES: u32 = 0x10000;
+}
+
+impl Display for AviStreamHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "Type: {}", self.fcc_type)?;
+        writeln!(f, "Codec: {}", self.fcc_handler)?;
+        if self.scale == 0 || self.rate == 0 {
+            writeln!(f, "Rate: unknown")?;
+            writeln!(f, "Length: {}", self.length)?;
+            write!(f, "Duration: unknown")
+        } else {
+            writeln!(f, "Rate: {:.2}/s", self.rate as f64 / self.scale as f64)?;
+            writeln!(f, "Length: {}", self.length)?;
+            let seconds = self.length as u64 * self.scale as u64 / self.rate as u64;
+            write!(f, "Duration: {}", Hms(seconds))
+        }
+    }
+}
+
 #[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[br(little)]
 pub struct Frame {
     pub left: i16,
@@ -82,10 +351,66 @@ pub struct Frame {
     pub bottom: i16,
 }
 
+/// An `RGBQUAD` palette color
+#[derive(BinRead, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct PaletteEntry {
+    pub blue: u8,
+    pub green: u8,
+    pub red: u8,
+    pub reserved: u8,
+}
+
+/// `AVIPALCHANGE` from a `##pc` chunk, replacing `entries.len()` colors of the stream
+/// palette starting at `first_entry`
+/// https://learn.microsoft.com/en-us/windows/win32/api/vfw/ns-vfw-avipalchange
+#[derive(BinRead, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct PaletteChange {
+    pub first_entry: u8,
+    /// 0 means all 256 entries
+    pub num_entries: u8,
+    pub flags: u16,
+    #[br(count = if num_entries == 0 { 256 } else { num_entries as usize })]
+    pub entries: Vec<PaletteEntry>,
+}
+
+/// A chapter from [`AviParser::chapter_marks`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChapterMark {
+    pub time_us: u64,
+    /// From the `labl` of the cue point, empty if it has none
+    pub name: String,
+}
+
+/// `cue ` chunk, as in WAVE files
+#[binread]
+#[derive(Debug)]
+#[br(little)]
+struct CueList {
+    #[br(temp)]
+    count: u32,
+    #[br(count = count)]
+    points: Vec<CuePoint>,
+}
+
+// Ignore the fccChunk, dwChunkStart, dwBlockStart and dwSampleOffset that follow
+#[derive(BinRead, Debug)]
+#[br(little)]
+struct CuePoint {
+    id: u32,
+    #[br(pad_after = 16)]
+    position: u32,
+}
+
 /// https://learn.microsoft.com/en-us/previous-versions/visualstudio/visual-studio-2012/z5731wbz(v=vs.110)
 /// https://learn.microsoft.com/en-us/previous-versions/dd183376(v=vs.85)
 // Ignore RGBQUAD bmiColors[1] array at end
 #[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[br(little)]
 pub struct BitmapInfo {
     pub size: u32,
@@ -99,24 +424,123 @@ pub struct BitmapInfo {
     pub y_pels_per_meter: i32,
     pub clr_used: u32,
     pub clr_important: u32,
+    /// Red, green and blue masks following the header (or inside a V4/V5 header)
+    /// for `BI_BITFIELDS`
+    #[br(if(compression == BitmapInfo::BI_BITFIELDS))]
+    pub bitfield_masks: Option<[u32; 3]>,
+}
+
+impl BitmapInfo {
+    pub const BI_RGB: u32 = 0;
+    pub const BI_RLE8: u32 = 1;
+    pub const BI_RLE4: u32 = 2;
+    pub const BI_BITFIELDS: u32 = 3;
+
+    pub fn pixel_format(&self) -> PixelFormat {
+        const YUV: &[[u8; 4]] = &[
+            *b"YUY2", *b"YUYV", *b"YVYU", *b"UYVY", *b"HDYC", *b"Y41P", *b"YV12", *b"I420",
+            *b"IYUV", *b"NV12", *b"NV21", *b"Y800", *b"v210",
+        ];
+        match self.compression {
+            Self::BI_RGB => PixelFormat::Rgb,
+            Self::BI_BITFIELDS => {
+                let [red_mask, green_mask, blue_mask] = self.bitfield_masks.unwrap_or_default();
+                PixelFormat::Bitfields {
+                    red_mask,
+                    green_mask,
+                    blue_mask,
+                }
+            }
+            // BI_RLE*, BI_JPEG, BI_PNG and other small constants are not fourccs
+            compression if compression < 0x100 => PixelFormat::Unknown(compression),
+            compression => {
+                let fourcc = Fourcc::from_u32(compression);
+                match &fourcc.bytes() {
+                    b"RGB " | b"raw " => PixelFormat::Rgb,
+                    b"BGR " => PixelFormat::Bgr,
+                    bytes if YUV.contains(bytes) => PixelFormat::Yuv(fourcc),
+                    _ => PixelFormat::Compressed(fourcc),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PixelFormat {
+    /// Uncompressed `BI_RGB`, DIB pixels are stored in BGR byte order
+    Rgb,
+    Bgr,
+    Bitfields {
+        red_mask: u32,
+        green_mask: u32,
+        blue_mask: u32,
+    },
+    /// Uncompressed YUV, e.g. `YUY2`
+    Yuv(Fourcc),
+    /// A codec fourcc, e.g. `MJPG`
+    Compressed(Fourcc),
+    Unknown(u32),
 }
 
 /// https://learn.microsoft.com/en-us/previous-versions/ms788112(v=vs.85)
-#[derive(BinRead, Clone, Debug)]
-#[br(little)]
+#[derive(BinRead, BinWrite, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[brw(little)]
 pub enum WaveFormat {
-    #[br(magic = 0x0001u16)]
+    #[brw(magic = 0x0001u16)]
     Pcm(WaveFormatEx),
-    #[br(magic = 0xfffeu16)]
+    #[brw(magic = 0xfffeu16)]
     Extensible(WaveFormatExtensible),
-    #[br(magic = 0x0050u16)]
+    #[brw(magic = 0x0050u16)]
     Mpeg1(Mpeg1WaveFormat),
-    #[br(magic = 0x0055u16)]
+    #[brw(magic = 0x0055u16)]
     Mp3(Mp3WaveFormat),
 }
 
-#[derive(BinRead, Clone, Debug)]
-#[br(little)]
+impl WaveFormat {
+    pub fn format(&self) -> &WaveFormatEx {
+        match self {
+            WaveFormat::Pcm(format) => format,
+            WaveFormat::Extensible(extensible) => &extensible.format,
+            WaveFormat::Mpeg1(mpeg1) => &mpeg1.format,
+            WaveFormat::Mp3(mp3) => &mp3.format,
+        }
+    }
+}
+
+/// e.g. `PCM 44100 Hz, 2 ch, 16-bit` or `MP3 128 kbps`. Compressed formats without
+/// `av_bytes_per_sec` show the sample rate and channels instead of the bitrate.
+impl Display for WaveFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WaveFormat::Pcm(_) => write!(f, "PCM ")?,
+            WaveFormat::Extensible(extensible) => match extensible.sub_format.known_format() {
+                Some(name) => write!(f, "{name} ")?,
+                None => write!(f, "Extensible {} ", extensible.sub_format.to_uuid_string())?,
+            },
+            WaveFormat::Mpeg1(_) => write!(f, "MPEG-1 ")?,
+            WaveFormat::Mp3(_) => write!(f, "MP3 ")?,
+        }
+        let format = self.format();
+        match self {
+            WaveFormat::Pcm(_) | WaveFormat::Extensible(_) => write!(
+                f,
+                "{} Hz, {} ch, {}-bit",
+                format.samples_per_sec, format.channels, format.bits_per_sample
+            ),
+            _ if format.av_bytes_per_sec == 0 => {
+                write!(f, "{} Hz, {} ch", format.samples_per_sec, format.channels)
+            }
+            _ => write!(f, "{} kbps", format.av_bytes_per_sec as u64 * 8 / 1000),
+        }
+    }
+}
+
+#[derive(BinRead, BinWrite, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[brw(little)]
 pub struct WaveFormatEx {
     pub channels: u16,
     pub samples_per_sec: u32,
@@ -127,8 +551,23 @@ pub struct WaveFormatEx {
     pub size: u16,
 }
 
-#[derive(BinRead, Clone, Debug)]
-#[br(little)]
+impl WaveFormatEx {
+    /// Duration of one sample in nanoseconds, 0 if `samples_per_sec` is 0
+    pub fn sample_duration_ns(&self) -> u64 {
+        1_000_000_000u64
+            .checked_div(self.samples_per_sec as u64)
+            .unwrap_or(0)
+    }
+
+    /// Size of one sample across all channels, 0 for compressed formats without `bits_per_sample`
+    pub fn frame_size_bytes(&self) -> u16 {
+        (self.bits_per_sample / 8).saturating_mul(self.channels)
+    }
+}
+
+#[derive(BinRead, BinWrite, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[brw(little)]
 pub struct WaveFormatExtensible {
     pub format: WaveFormatEx,
     // union {
@@ -141,8 +580,9 @@ pub struct WaveFormatExtensible {
     pub sub_format: Guid,
 }
 
-#[derive(BinRead, Clone, Debug)]
-#[br(little)]
+#[derive(BinRead, BinWrite, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[brw(little)]
 pub struct Guid {
     pub data1: u32,
     pub data2: u16,
@@ -150,8 +590,55 @@ pub struct Guid {
     pub data4: [u8; 8],
 }
 
-#[derive(BinRead, Clone, Debug)]
-#[br(little)]
+impl Guid {
+    /// `KSDATAFORMAT_SUBTYPE_*` sub formats are a `WaveFormatEx` format tag in `data1`
+    /// followed by this common suffix
+    const SUBTYPE_DATA2: u16 = 0x0000;
+    const SUBTYPE_DATA3: u16 = 0x0010;
+    const SUBTYPE_DATA4: [u8; 8] = [0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71];
+
+    const KNOWN_SUBTYPES: &[(u32, &'static str)] = &[
+        (0x0001, "PCM"),
+        (0x0002, "ADPCM"),
+        (0x0003, "IEEE_FLOAT"),
+        (0x0006, "ALAW"),
+        (0x0007, "MULAW"),
+        (0x0008, "DTS"),
+        (0x0050, "MPEG"),
+        (0x0055, "MPEG_LAYER3"),
+        (0x0092, "DOLBY_AC3_SPDIF"),
+        (0x0164, "WMA_SPDIF"),
+        (0x1610, "MPEG_HEAAC"),
+    ];
+
+    /// Format as `00000001-0000-0010-8000-00AA00389B71`. The fields hold the mixed endian
+    /// Microsoft layout, so this is not the RFC 4122 byte order of the raw bytes.
+    pub fn to_uuid_string(&self) -> String {
+        let d = &self.data4;
+        format!(
+            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+            self.data1, self.data2, self.data3, d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7]
+        )
+    }
+
+    /// Name of a common `KSDATAFORMAT_SUBTYPE_*` audio sub format, e.g. `PCM`
+    pub fn known_format(&self) -> Option<&'static str> {
+        if self.data2 != Self::SUBTYPE_DATA2
+            || self.data3 != Self::SUBTYPE_DATA3
+            || self.data4 != Self::SUBTYPE_DATA4
+        {
+            return None;
+        }
+        Self::KNOWN_SUBTYPES
+            .iter()
+            .find(|(format_tag, _)| *format_tag == self.data1)
+            .map(|(_, name)| *name)
+    }
+}
+
+#[derive(BinRead, BinWrite, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[brw(little)]
 pub struct Mpeg1WaveFormat {
     pub format: WaveFormatEx,
     pub head_layer: u16,
@@ -164,8 +651,9 @@ pub struct Mpeg1WaveFormat {
     pub pts_high: u32,
 }
 
-#[derive(BinRead, Clone, Debug)]
-#[br(little)]
+#[derive(BinRead, BinWrite, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[brw(little)]
 pub struct Mp3WaveFormat {
     pub format: WaveFormatEx,
     pub id: u16,
@@ -176,21 +664,129 @@ pub struct Mp3WaveFormat {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StreamInfo {
     Audio(AudioStream),
     Video(VideoStream),
+    Text(TextStream),
+    Other(OtherStream),
+}
+
+impl StreamInfo {
+    pub fn stream_header(&self) -> &AviStreamHeader {
+        match self {
+            StreamInfo::Audio(a) => &a.stream_header,
+            StreamInfo::Video(v) => &v.stream_header,
+            StreamInfo::Text(t) => &t.stream_header,
+            StreamInfo::Other(o) => &o.stream_header,
+        }
+    }
+
+    /// Video bitrate is estimated from `size_image` at the stream frame rate,
+    /// audio uses `av_bytes_per_sec` falling back to `block_align * samples_per_sec`
+    pub fn average_bitrate_bps(&self) -> Option<u64> {
+        match self {
+            StreamInfo::Video(v) => {
+                let header = &v.stream_header;
+                if header.scale == 0 {
+                    return None;
+                }
+                // Wide enough for any header values, `None` if the result isn't
+                let bps = v.bitmap_info.size_image as u128 * 8 * header.rate as u128
+                    / header.scale as u128;
+                u64::try_from(bps).ok()
+            }
+            StreamInfo::Audio(a) => {
+                let format = a.wave_format.format();
+                let bytes_per_sec = if format.av_bytes_per_sec != 0 {
+                    format.av_bytes_per_sec as u64
+                } else {
+                    format.block_align as u64 * format.samples_per_sec as u64
+                };
+                bytes_per_sec.checked_mul(8)
+            }
+            StreamInfo::Text(_) | StreamInfo::Other(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bitrate {
+    pub bps: u64,
 }
 
+impl Bitrate {
+    pub fn as_kbps(&self) -> f64 {
+        self.bps as f64 / 1_000.0
+    }
+
+    pub fn as_mbps(&self) -> f64 {
+        self.bps as f64 / 1_000_000.0
+    }
+}
+
+impl From<u64> for Bitrate {
+    fn from(bps: u64) -> Self {
+        Self { bps }
+    }
+}
+
+/// Converts the audio of a stream for `AviParser::transcode_audio`, e.g. by calling an
+/// external codec, so the crate doesn't depend on any
+pub trait AudioEncoder {
+    /// Format of the encoded audio, the input `format` unchanged by default
+    fn output_format(&self, format: &WaveFormat) -> WaveFormat {
+        format.clone()
+    }
+
+    /// Encode the data of one `movi` chunk of audio in `format`
+    fn encode(&mut self, format: &WaveFormat, chunk_data: &[u8]) -> Result<Vec<u8>, EncoderError>;
+
+    /// Flush data the encoder buffered after the last chunk, nothing by default
+    fn finish(&mut self, format: &WaveFormat) -> Result<Vec<u8>, EncoderError> {
+        let _ = format;
+        Ok(Vec::new())
+    }
+}
+
+/// Failure of an `AudioEncoder`, returned by `transcode_audio` as a `RiffError::Custom`
+/// at the position of the chunk being encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncoderError {
+    pub message: String,
+}
+
+impl EncoderError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for EncoderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "audio encoder failed: {}", self.message)
+    }
+}
+
+impl core::error::Error for EncoderError {}
+
 pub trait Stream {
     fn stream_id(&self) -> Fourcc;
     fn stream_header(&self) -> &AviStreamHeader;
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AudioStream {
     pub stream_id: Fourcc,
     pub stream_header: AviStreamHeader,
     pub wave_format: WaveFormat,
+    /// Codec specific data from `strd`
+    pub strd: Option<Vec<u8>>,
+    /// Stream name from `strn`
+    pub strn: Option<String>,
 }
 
 impl<'a> TryFrom<&'a StreamInfo> for &'a AudioStream {
@@ -204,6 +800,46 @@ impl<'a> TryFrom<&'a StreamInfo> for &'a AudioStream {
     }
 }
 
+impl AudioStream {
+    /// `stream_header.length` counts samples for PCM, bytes for constant bitrate compressed audio
+    /// and blocks for variable bitrate audio (`sample_size == 0`), so samples are derived from the
+    /// stream duration `length * scale / rate`. PCM counts, including extensible PCM, include
+    /// every channel. Saturates at `u64::MAX` for crafted header values.
+    pub fn total_samples(&self) -> u64 {
+        let header = &self.stream_header;
+        if header.rate == 0 {
+            return 0;
+        }
+        let format = self.wave_format.format();
+        let samples = header.length as u128 * header.scale as u128 * format.samples_per_sec as u128
+            / header.rate as u128;
+        let samples = match &self.wave_format {
+            WaveFormat::Pcm(_) => samples * format.channels as u128,
+            WaveFormat::Extensible(extensible)
+                if extensible.sub_format.known_format() == Some("PCM") =>
+            {
+                samples * format.channels as u128
+            }
+            _ => samples,
+        };
+        u64::try_from(samples).unwrap_or(u64::MAX)
+    }
+
+    /// Samples per channel the audio leads the video by in interleaved files,
+    /// `initial_frames * scale * samples_per_sec / rate` as `initial_frames` is in stream units
+    pub fn preroll_samples(&self) -> u32 {
+        let header = &self.stream_header;
+        if header.rate == 0 {
+            return 0;
+        }
+        let samples = header.initial_frames as u64
+            * header.scale as u64
+            * self.wave_format.format().samples_per_sec as u64
+            / header.rate as u64;
+        u32::try_from(samples).unwrap_or(u32::MAX)
+    }
+}
+
 impl Stream for AudioStream {
     fn stream_id(&self) -> Fourcc {
         self.stream_id
@@ -215,10 +851,16 @@ impl Stream for AudioStream {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VideoStream {
     pub stream_id: Fourcc,
     pub stream_header: AviStreamHeader,
     pub bitmap_info: BitmapInfo,
+    pub video_properties: Option<VideoProperties>,
+    /// Codec specific data from `strd`
+    pub strd: Option<Vec<u8>>,
+    /// Stream name from `strn`
+    pub strn: Option<String>,
 }
 
 impl<'a> TryFrom<&'a StreamInfo> for &'a VideoStream {
@@ -242,53 +884,216 @@ impl Stream for VideoStream {
     }
 }
 
+/// A `txts` subtitle stream. Its chunks hold raw UTF-8 or SRT formatted text.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextStream {
+    pub stream_id: Fourcc,
+    pub stream_header: AviStreamHeader,
+    /// Stream name from `strn`, often the subtitle language
+    pub strn: Option<String>,
+}
+
+impl<'a> TryFrom<&'a StreamInfo> for &'a TextStream {
+    type Error = ();
+
+    fn try_from(value: &'a StreamInfo) -> Result<Self, Self::Error> {
+        match value {
+            StreamInfo::Text(t) => Ok(t),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Stream for TextStream {
+    fn stream_id(&self) -> Fourcc {
+        self.stream_id
+    }
+
+    fn stream_header(&self) -> &AviStreamHeader {
+        &self.stream_header
+    }
+}
+
+/// A stream that is neither audio, video nor text, e.g. MIDI.
+/// Its `strf` format data is kept unparsed.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OtherStream {
+    pub stream_index: u32,
+    pub stream_header: AviStreamHeader,
+    pub format: Vec<u8>,
+    /// Codec specific data from `strd`
+    pub strd: Option<Vec<u8>>,
+    /// Stream name from `strn`
+    pub strn: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationWarning {
+    /// `avih` declares a different number of streams than were parsed
+    StreamCountMismatch { declared: u32, parsed: usize },
+    /// A video stream length disagrees with the file total frame count
+    FrameCountMismatch {
+        stream_index: usize,
+        total_frames: u32,
+        stream_length: u32,
+    },
+    /// The `movi` list extends past the end of the file
+    MoviOutOfBounds { end: u64, file_size: u64 },
+    /// A `movi` chunk id does not start with a valid two digit stream index
+    InvalidStreamId { position: u64, id: Fourcc },
+    /// `suggested_buffer_size` is zero in `avih` (`stream_index` is `None`) or a stream header
+    ZeroSuggestedBufferSize { stream_index: Option<usize> },
+}
+
+/// How `AviParser::find_best_stream_with_policy` chooses between streams of equal `priority`,
+/// which many encoders leave at zero for every stream. The last stream in `hdrl` order wins
+/// remaining ties.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum StreamSelectionPolicy {
+    /// Priority only
+    #[default]
+    HighestPriority,
+    /// The highest resolution video or highest average bitrate audio
+    BestQuality,
+    /// A stream whose `fcc_handler`, or video `compression`, is the codec
+    PreferCodec(Fourcc),
+}
+
+/// A structural problem found by `AviParser::detect_corruption`, with the byte offset of
+/// the header it concerns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorruptionWarning {
+    /// A chunk or list extends past the end of the list containing it
+    ChunkPastListEnd { position: u64, description: String },
+    /// `avih` declares a different number of streams than `hdrl` has `strl` lists
+    StreamCountMismatch { position: u64, description: String },
+    /// Two streams share the two digit prefix of their `movi` chunk ids
+    DuplicateStreamId { position: u64, description: String },
+    /// The `movi` list holds no chunks
+    EmptyMovi { position: u64, description: String },
+    /// `avih` total frames is zero although there is a video stream and `movi` holds chunks
+    ZeroTotalFrames { position: u64, description: String },
+}
+
+impl CorruptionWarning {
+    pub fn position(&self) -> u64 {
+        match self {
+            CorruptionWarning::ChunkPastListEnd { position, .. }
+            | CorruptionWarning::StreamCountMismatch { position, .. }
+            | CorruptionWarning::DuplicateStreamId { position, .. }
+            | CorruptionWarning::EmptyMovi { position, .. }
+            | CorruptionWarning::ZeroTotalFrames { position, .. } => *position,
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            CorruptionWarning::ChunkPastListEnd { description, .. }
+            | CorruptionWarning::StreamCountMismatch { description, .. }
+            | CorruptionWarning::DuplicateStreamId { description, .. }
+            | CorruptionWarning::EmptyMovi { description, .. }
+            | CorruptionWarning::ZeroTotalFrames { description, .. } => description,
+        }
+    }
+}
+
+impl Display for CorruptionWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "0x{:x}: {}", self.position(), self.description())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterleavingReport {
+    /// Number of `movi` chunks of each stream, by stream index
+    pub chunk_counts: Vec<usize>,
+    /// Largest number of bytes between consecutive chunks of a video stream
+    pub max_video_gap_bytes: u64,
+    /// Largest number of bytes between consecutive chunks of an audio stream
+    pub max_audio_gap_bytes: u64,
+    pub well_interleaved: bool,
+}
+
+/// What `AviParser::repair` changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// `avih` total frames before and after, if it didn't match the video chunks in `movi`
+    pub total_frames: Option<(u32, u32)>,
+    /// The source had no `idx1`, rather than one that was replaced
+    pub index_missing: bool,
+    /// Entries in the rebuilt `idx1`
+    pub index_entries: usize,
+    /// `LIST`s whose size didn't match the items they contain. `RIFF` sizes are always rewritten.
+    pub list_sizes_corrected: usize,
+    /// Odd sized chunks, each written with a pad byte
+    pub padded_chunks: usize,
+}
+
 pub struct AviParser<R> {
     parser: RiffParser<R>,
+    /// The `RIFF AVI ` list, which need not start at offset 0 of the reader
+    riff: Riff<List>,
+    /// `total_frames` is the OpenDML count from `dmlh` when present
     pub avi_header: AviMainHeader,
+    pub odml_header: Option<OdmlHeader>,
     pub stream_info: Vec<StreamInfo>,
+    /// `LIST INFO` metadata preceding `movi`
+    pub info: Option<InfoList>,
     pub movi: Riff<List>,
+    pub idx1: Option<Riff<Chunk>>,
+    /// `movi` lists of any OpenDML `RIFF AVIX` segments following the main `RIFF AVI `
+    pub extended_movi: Vec<Riff<List>>,
 }
 
 impl<R: Read + Seek> AviParser<R> {
-    pub fn new(parser: RiffParser<R>) -> Result<Self, Error> {
+    pub fn new(parser: RiffParser<R>) -> RiffResult<Self> {
         let riff = parser.riff()?;
-        Self::validate_tag(&riff, tag::AVI)?;
+        validate_tag(&riff, tag::AVI)?;
 
-        let mut avi_iter = parser.chunks(riff);
-        let RiffType::List(hdrl) = avi_iter.next().ok_or_else(Self::eof_error)?? else {
-            return Err(Self::missing_error(avi_iter.position(), tag::HDRL));
-        };
-        Self::validate_tag(&hdrl, tag::HDRL)?;
+        let mut avi_iter = parser.chunks(riff).peekable_chunk();
+        let hdrl = next_list(&mut avi_iter, tag::HDRL)?;
 
-        let mut hdrl_iter = parser.chunks(hdrl);
-        let RiffType::Chunk(avih) = hdrl_iter.next().ok_or_else(Self::eof_error)?? else {
-            return Err(Self::missing_error(hdrl_iter.position(), tag::AVIH));
-        };
-        Self::validate_tag(&avih, tag::AVIH)?;
+        let mut hdrl_iter = parser.chunks(hdrl).peekable_chunk();
+        let avih = next_chunk(&mut hdrl_iter, tag::AVIH)?;
 
-        let main_header = parser.read_data_struct::<AviMainHeader>(avih)?;
+        let mut main_header = parser.read_data_struct::<AviMainHeader>(avih)?;
         let mut stream_info = Vec::with_capacity(main_header.streams as usize);
 
         for stream_index in 0..main_header.streams {
-            let RiffType::List(strl) = hdrl_iter.next().ok_or_else(Self::eof_error)?? else {
-                return Err(Self::missing_error(hdrl_iter.position(), tag::STRL));
-            };
-            Self::validate_tag(&strl, tag::STRL)?;
+            let strl = next_list(&mut hdrl_iter, tag::STRL)?;
 
-            let mut strl_iter = parser.chunks(strl);
-            let RiffType::Chunk(strh) = strl_iter.next().ok_or_else(Self::eof_error)?? else {
-                return Err(Self::missing_error(strl_iter.position(), tag::STRH));
-            };
-            Self::validate_tag(&strh, tag::STRH)?;
+            let mut strl_iter = parser.chunks(strl).peekable_chunk();
+            let strh = next_chunk(&mut strl_iter, tag::STRH)?;
             let stream_header = parser.read_data_struct::<AviStreamHeader>(strh)?;
-            let RiffType::Chunk(strf) = strl_iter.next().ok_or_else(Self::eof_error)?? else {
-                return Err(Self::missing_error(strl_iter.position(), tag::STRF));
-            };
-            Self::validate_tag(&strf, tag::STRF)?;
+            let strf = next_chunk(&mut strl_iter, tag::STRF)?;
+
+            let mut strd = None;
+            let mut strn = None;
+            let mut vprp = None;
+            for result in strl_iter {
+                let RiffType::Chunk(chunk) = result? else {
+                    continue;
+                };
+                match chunk.id() {
+                    tag::STRD => strd = Some(parser.read_data_vec(chunk)?),
+                    tag::STRN => {
+                        let name = parser.read_data_vec(chunk)?;
+                        let name = name.split(|&b| b == 0).next().unwrap_or_default();
+                        strn = Some(String::from_utf8_lossy(name).into_owned());
+                    }
+                    tag::VPRP => vprp = Some(chunk),
+                    _ => {}
+                }
+            }
 
             match stream_header.fcc_type {
                 tag::VIDS => {
                     let bitmap_info = parser.read_data_struct::<BitmapInfo>(strf)?;
+                    let video_properties = vprp
+                        .map(|vprp| parser.read_data_struct::<VideoProperties>(vprp))
+                        .transpose()?;
                     stream_info.push(StreamInfo::Video(VideoStream {
                         stream_id: tag::stream(
                             stream_index,
@@ -297,6 +1102,9 @@ impl<R: Read + Seek> AviParser<R> {
                         ),
                         stream_header,
                         bitmap_info,
+                        video_properties,
+                        strd,
+                        strn,
                     }));
                 }
                 tag::AUDS => {
@@ -305,88 +1113,1021 @@ impl<R: Read + Seek> AviParser<R> {
                         stream_id: tag::stream(stream_index, tag::DATA_AUDIO),
                         stream_header,
                         wave_format,
+                        strd,
+                        strn,
                     }));
                 }
-                _ => {}
+                tag::TXTS => {
+                    stream_info.push(StreamInfo::Text(TextStream {
+                        stream_id: tag::stream(stream_index, tag::DATA_TEXT),
+                        stream_header,
+                        strn,
+                    }));
+                }
+                _ => {
+                    let format = parser.read_data_vec(strf)?;
+                    stream_info.push(StreamInfo::Other(OtherStream {
+                        stream_index,
+                        stream_header,
+                        format,
+                        strd,
+                        strn,
+                    }));
+                }
+            }
+        }
+
+        let mut odml_header = None;
+        for result in hdrl_iter {
+            if let RiffType::List(odml) = result?
+                && odml.id() == tag::ODML
+            {
+                odml_header = parser
+                    .chunks(odml)
+                    .find_map(|result| match result {
+                        Ok(RiffType::Chunk(dmlh)) if dmlh.id() == tag::DMLH => {
+                            Some(parser.read_data_struct::<OdmlHeader>(dmlh))
+                        }
+                        Err(e) => Some(Err(e)),
+                        _ => None,
+                    })
+                    .transpose()?;
             }
         }
+        if let Some(odml_header) = &odml_header {
+            main_header.total_frames = odml_header.total_frames;
+        }
+
+        let mut info = None;
+        let movi = loop {
+            match avi_iter.next().ok_or_else(eof_error)?? {
+                RiffType::List(movi) if movi.id() == tag::MOVI => break movi,
+                RiffType::List(list) if list.id() == crate::tag::INFO => {
+                    info = Some(InfoList::read(&parser, list)?);
+                }
+                _ => {}
+            }
+        };
+        let idx1 = match avi_iter.next() {
+            Some(Ok(RiffType::Chunk(idx1))) if idx1.id() == tag::IDX1 => Some(idx1),
+            _ => None,
+        };
 
-        let movi = avi_iter
-            .find_map(|result| match result {
+        let file_size = parser.stream_len()?;
+        let mut extended_movi = Vec::new();
+        let mut next_riff = riff.end_position();
+        // RIFF header plus form type
+        while next_riff + 12 <= file_size {
+            let Ok(avix) = parser.riff_at(next_riff) else {
+                break;
+            };
+            if avix.id() != tag::AVIX {
+                break;
+            }
+            let movi = parser.chunks(avix).find_map(|result| match result {
                 Ok(RiffType::List(movi)) if movi.id() == tag::MOVI => Some(Ok(movi)),
                 Err(e) => Some(Err(e)),
                 _ => None,
-            })
-            .ok_or_else(Self::eof_error)??;
+            });
+            if let Some(movi) = movi.transpose()? {
+                extended_movi.push(movi);
+            }
+            next_riff = avix.end_position();
+        }
 
         Ok(Self {
             parser,
+            riff,
             avi_header: main_header,
+            odml_header,
             stream_info,
+            info,
             movi,
+            idx1,
+            extended_movi,
+        })
+    }
+
+    /// The OpenDML total frame count if present, otherwise the main header count
+    /// which only covers the first `RIFF AVI ` of files larger than 1GB. `avi_header`
+    /// already holds this count, as `AviParser::new` overrides it from `dmlh`.
+    pub fn true_total_frames(&self) -> u32 {
+        self.odml_header
+            .as_ref()
+            .map_or(self.avi_header.total_frames, |odml| odml.total_frames)
+    }
+
+    /// Presentation timestamp in microseconds of each frame of the best video stream,
+    /// `(start + frame_index) * micro_sec_per_frame` using `start` and `length` from the stream
+    /// header. Without a video stream `total_frames` timestamps are returned starting at 0.
+    ///
+    /// AVI has no per frame timing, so these are constant interval timestamps. Variable frame
+    /// rate files can only be timed from their `idx1` entries, e.g. by skipped frames.
+    pub fn video_timestamps(&self) -> impl Iterator<Item = u64> {
+        let micro_sec_per_frame = self.avi_header.micro_sec_per_frame as u64;
+        let (start, frames) = self
+            .find_best_stream::<VideoStream>()
+            .map_or((0, self.true_total_frames()), |video| {
+                (video.stream_header.start, video.stream_header.length)
+            });
+        (start as u64..start as u64 + frames as u64).map(move |frame| frame * micro_sec_per_frame)
+    }
+
+    /// Check the parsed headers and the `movi` chunk headers for common structural problems.
+    /// Chunk data is not read.
+    pub fn validate(&self) -> RiffResult<Vec<ValidationWarning>> {
+        let mut warnings = Vec::new();
+
+        if self.avi_header.streams as usize != self.stream_info.len() {
+            warnings.push(ValidationWarning::StreamCountMismatch {
+                declared: self.avi_header.streams,
+                parsed: self.stream_info.len(),
+            });
+        }
+        if self.avi_header.suggested_buffer_size == 0 {
+            warnings.push(ValidationWarning::ZeroSuggestedBufferSize { stream_index: None });
+        }
+
+        let total_frames = self.true_total_frames();
+        for (stream_index, stream) in self.stream_info.iter().enumerate() {
+            let stream_header = stream.stream_header();
+            if let StreamInfo::Video(_) = stream
+                && stream_header.length != total_frames
+            {
+                warnings.push(ValidationWarning::FrameCountMismatch {
+                    stream_index,
+                    total_frames,
+                    stream_length: stream_header.length,
+                });
+            }
+            if stream_header.suggested_buffer_size == 0 {
+                warnings.push(ValidationWarning::ZeroSuggestedBufferSize {
+                    stream_index: Some(stream_index),
+                });
+            }
+        }
+
+        let file_size = self.parser.stream_len()?;
+        let end = self.movi.position() + self.movi.data_size() as u64;
+        if end > file_size {
+            warnings.push(ValidationWarning::MoviOutOfBounds { end, file_size });
+        }
+
+        // Stop at a truncated chunk header, MoviOutOfBounds already covers it
+        let mut movi_iter = self.parser.chunks(self.movi);
+        while movi_iter.position() + 8 <= file_size
+            && let Some(result) = movi_iter.next()
+        {
+            let RiffType::Chunk(chunk) = result? else {
+                continue;
+            };
+            let id = chunk.id();
+            if id == tag::JUNK {
+                continue;
+            }
+            let bytes = id.bytes();
+            // Index chunks are named ix##
+            let digits = if bytes.starts_with(b"ix") {
+                [bytes[2], bytes[3]]
+            } else {
+                [bytes[0], bytes[1]]
+            };
+            if stream_index(digits).is_none_or(|index| index >= self.stream_info.len()) {
+                warnings.push(ValidationWarning::InvalidStreamId {
+                    position: chunk.position(),
+                    id,
+                });
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    /// Scan the list and chunk headers of the `RIFF AVI ` list for common corruption.
+    /// Chunk data is not read and nothing is written. Scanning a list stops at the first
+    /// header that can't be read or extends past the end of the list.
+    pub fn detect_corruption(&self) -> Vec<CorruptionWarning> {
+        let mut warnings = Vec::new();
+        let riff = self.riff;
+        self.detect_list_overruns(riff, &mut warnings);
+
+        if let Ok(Some(hdrl)) = self.parser.find_list(riff, tag::HDRL) {
+            let strls = self
+                .parser
+                .chunks(hdrl)
+                .map_while(Result::ok)
+                .filter_map(|item| match item {
+                    RiffType::List(strl) if strl.id() == tag::STRL => Some(strl),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            if strls.len() != self.avi_header.streams as usize {
+                warnings.push(CorruptionWarning::StreamCountMismatch {
+                    position: hdrl.header_position(),
+                    description: format!(
+                        "avih declares {} streams but hdrl has {} strl lists",
+                        self.avi_header.streams,
+                        strls.len()
+                    ),
+                });
+            }
+
+            let mut prefixes = Vec::with_capacity(self.stream_info.len());
+            for (stream_index, stream) in self.stream_info.iter().enumerate() {
+                let prefix = match stream {
+                    StreamInfo::Video(v) => v.stream_id.bytes(),
+                    StreamInfo::Audio(a) => a.stream_id.bytes(),
+                    StreamInfo::Text(t) => t.stream_id.bytes(),
+                    StreamInfo::Other(o) => tag::stream(o.stream_index, [0; 2]).bytes(),
+                };
+                let prefix = [prefix[0], prefix[1]];
+                if prefixes.contains(&prefix) {
+                    warnings.push(CorruptionWarning::DuplicateStreamId {
+                        position: strls.get(stream_index).unwrap_or(&hdrl).header_position(),
+                        description: format!(
+                            "stream {stream_index} reuses stream id {}",
+                            String::from_utf8_lossy(&prefix)
+                        ),
+                    });
+                }
+                prefixes.push(prefix);
+            }
+
+            // Audio only files may leave total frames at zero
+            if self.avi_header.total_frames == 0
+                && self.movi.data_size() > 0
+                && self.find_best_stream::<VideoStream>().is_some()
+                && let Ok(Some(avih)) = self.parser.find_chunk(hdrl, tag::AVIH)
+            {
+                warnings.push(CorruptionWarning::ZeroTotalFrames {
+                    position: avih.header_position(),
+                    description: format!(
+                        "avih total frames is 0 but movi holds {} bytes",
+                        self.movi.data_size()
+                    ),
+                });
+            }
+        }
+
+        if self.movi.data_size() == 0 {
+            warnings.push(CorruptionWarning::EmptyMovi {
+                position: self.movi.header_position(),
+                description: "movi list is empty".into(),
+            });
+        }
+        warnings
+    }
+
+    fn detect_list_overruns(&self, list: Riff<List>, warnings: &mut Vec<CorruptionWarning>) {
+        let list_end = list.byte_range().end;
+        for item in self.parser.chunks(list) {
+            let Ok(item) = item else {
+                return;
+            };
+            // Pad bytes are not counted, writers often drop the pad of the last chunk
+            let (position, end) = match item {
+                RiffType::List(list) => (list.header_position(), list.byte_range().end),
+                RiffType::Chunk(chunk) => (chunk.header_position(), chunk.byte_range().end),
+            };
+            if end > list_end {
+                warnings.push(CorruptionWarning::ChunkPastListEnd {
+                    position,
+                    description: format!(
+                        "{} ends at 0x{end:x}, past the end of {} at 0x{list_end:x}",
+                        item.id(),
+                        list.id()
+                    ),
+                });
+                return;
+            }
+            if let RiffType::List(list) = item {
+                self.detect_list_overruns(list, warnings);
+            }
+        }
+    }
+
+    /// Write the audio stream `stream_id` to `writer` as a `RIFF WAVE` file,
+    /// the `fmt ` chunk is the stream `WaveFormat` and `data` holds every `movi` chunk
+    pub fn export_wav<W: Write + Seek>(&self, stream_id: Fourcc, writer: &mut W) -> RiffResult<()> {
+        let audio = self.audio_stream(stream_id)?;
+        let mut wav = WavWriter::with_format(writer, &audio.wave_format)?;
+        for chunk in self.movi_chunks(stream_id) {
+            wav.write_samples(&self.parser.read_data_vec(chunk?)?)?;
+        }
+        wav.finalize()?;
+        Ok(())
+    }
+
+    /// Like `export_wav`, passing the data of every chunk through `encoder`.
+    /// The `fmt ` chunk holds the `AudioEncoder::output_format`.
+    pub fn transcode_audio<E: AudioEncoder, W: Write + Seek>(
+        &self,
+        stream_id: Fourcc,
+        mut encoder: E,
+        writer: &mut W,
+    ) -> RiffResult<()> {
+        let audio = self.audio_stream(stream_id)?;
+        let format = &audio.wave_format;
+        let encoder_error = |position: u64, err: EncoderError| {
+            RiffError::Custom(BinError::Custom {
+                pos: position,
+                err: Box::new(err),
+            })
+        };
+        let mut wav = WavWriter::with_format(writer, &encoder.output_format(format))?;
+        for chunk in self.movi_chunks(stream_id) {
+            let chunk = chunk?;
+            let data = self.parser.read_data_vec(chunk)?;
+            let encoded = encoder
+                .encode(format, &data)
+                .map_err(|err| encoder_error(chunk.position(), err))?;
+            wav.write_samples(&encoded)?;
+        }
+        let tail = encoder
+            .finish(format)
+            .map_err(|err| encoder_error(self.movi.end_position(), err))?;
+        wav.write_samples(&tail)?;
+        wav.finalize()?;
+        Ok(())
+    }
+
+    fn audio_stream(&self, stream_id: Fourcc) -> RiffResult<&AudioStream> {
+        self.stream_info
+            .iter()
+            .find_map(|stream| match stream {
+                StreamInfo::Audio(audio) if audio.stream_id == stream_id => Some(audio),
+                _ => None,
+            })
+            .ok_or_else(|| missing_error(self.movi.position(), stream_id))
+    }
+
+    /// Call `callback` with the index and data of every chunk of the video stream `stream_id`,
+    /// in `movi` order. Iteration stops at the first error, including one from `callback`.
+    pub fn export_frames<F>(&self, stream_id: Fourcc, mut callback: F) -> RiffResult<()>
+    where
+        F: FnMut(usize, &[u8]) -> RiffResult<()>,
+    {
+        if !self.stream_info.iter().any(
+            |stream| matches!(stream, StreamInfo::Video(video) if video.stream_id == stream_id),
+        ) {
+            return Err(missing_error(self.movi.position(), stream_id));
+        }
+        for (index, chunk) in self.movi_chunks(stream_id).enumerate() {
+            let data = self.parser.read_data_vec(chunk?)?;
+            callback(index, &data)?;
+        }
+        Ok(())
+    }
+
+    /// Write every frame of the video stream `stream_id` to `dir` as `frame_000000.{extension}`,
+    /// e.g. `jpg` for MJPEG streams
+    #[cfg(feature = "std")]
+    pub fn export_frames_to_dir(
+        &self,
+        stream_id: Fourcc,
+        dir: &std::path::Path,
+        extension: &str,
+    ) -> RiffResult<()> {
+        self.export_frames(stream_id, |index, data| {
+            std::fs::write(dir.join(format!("frame_{index:06}.{extension}")), data)?;
+            Ok(())
+        })
+    }
+
+    /// Read the entries of `idx1`, if the file has one
+    pub fn read_index(&self) -> RiffResult<Option<AviIndex>> {
+        let Some(idx1) = self.idx1 else {
+            return Ok(None);
+        };
+        let entry_size = size_of::<AviIndexEntry>() as u32;
+        let mut entries = Vec::with_capacity((idx1.data_size() / entry_size) as usize);
+        let mut offset = 0;
+        while offset + entry_size <= idx1.data_size() {
+            entries.push(
+                self.parser
+                    .read_data_struct_at::<AviIndexEntry>(idx1, offset)?,
+            );
+            offset += entry_size;
+        }
+        Ok(Some(AviIndex { entries }))
+    }
+
+    /// Regenerate the `idx1` entries of the first `movi` list by scanning its chunks,
+    /// for files with a missing or corrupt index. Keyframes can't be told apart without
+    /// decoding, so every chunk is flagged as one.
+    pub fn rebuild_index(&self) -> RiffResult<AviIndex> {
+        let movi_start = self.movi.position() - size_of::<Fourcc>() as u64;
+        let mut entries = Vec::new();
+        for result in self.movi_iter(self.movi) {
+            let chunk = result?;
+            if chunk.id() == tag::JUNK {
+                continue;
+            }
+            entries.push(AviIndexEntry {
+                chunk_id: chunk.id(),
+                flags: AviIndexEntry::FLAG_KEYFRAME,
+                offset: (chunk.header_position() - movi_start) as u32,
+                size: chunk.data_size(),
+            });
+        }
+        Ok(AviIndex { entries })
+    }
+
+    /// Copy the file to `writer`, repairing common corruptions on the way:
+    /// - `avih` total frames is set to the number of video chunks in the first `movi`
+    /// - `idx1` is replaced with one rebuilt by `rebuild_index`, and `avih` flagged as indexed
+    /// - list sizes, including the `RIFF` sizes, are recomputed from their contents
+    /// - every chunk is word aligned with a pad byte after odd sized data
+    ///
+    /// OpenDML `RIFF AVIX` segments are copied after the first `RIFF AVI `. Headers are
+    /// written in the byte order of the file, so a `RIFX` file stays big endian. The file
+    /// is read through the reader this parser was created with, so it isn't passed again.
+    pub fn repair<W: Write + Seek>(&self, writer: &mut W) -> RiffResult<RepairReport> {
+        let index = self.rebuild_index()?;
+        let mut report = RepairReport {
+            index_missing: self.idx1.is_none(),
+            index_entries: index.entries.len(),
+            ..Default::default()
+        };
+        let mut total_frames = self.avi_header.total_frames;
+        if let Some(video) = self.find_best_stream::<VideoStream>() {
+            let [a, b, ..] = video.stream_id.bytes();
+            let uncompressed = Fourcc::new([a, b, b'd', b'b']);
+            let frames = index
+                .entries
+                .iter()
+                .filter(|entry| entry.chunk_id == video.stream_id || entry.chunk_id == uncompressed)
+                .count() as u32;
+            if frames != total_frames {
+                report.total_frames = Some((total_frames, frames));
+                total_frames = frames;
+            }
+        }
+
+        let endian = self.parser.endian();
+        let mut riff = self.riff;
+        loop {
+            let start = writer.stream_position()?;
+            let header = match endian {
+                Endian::Big => HeaderType::rifx(riff.id(), 0),
+                Endian::Little => HeaderType::riff(riff.id(), 0),
+            };
+            header.write_endian(writer, endian)?;
+            for item in self.parser.chunks(riff) {
+                match item? {
+                    RiffType::Chunk(idx1) if idx1.id() == tag::IDX1 => {}
+                    RiffType::List(movi) if movi.position() == self.movi.position() => {
+                        self.repair_list(movi, total_frames, writer, &mut report)?;
+                        let data = index.to_bytes();
+                        HeaderType::chunk(tag::IDX1, data.len() as u32)
+                            .write_endian(writer, endian)?;
+                        writer.write_all(&data)?;
+                    }
+                    RiffType::List(list) => {
+                        self.repair_list(list, total_frames, writer, &mut report)?
+                    }
+                    RiffType::Chunk(chunk) => {
+                        self.repair_chunk(chunk, total_frames, writer, &mut report)?
+                    }
+                }
+            }
+            // The RIFF size changes with the index, so isn't reported
+            patch_list_size(writer, start, riff, endian)?;
+
+            let next = riff.end_position();
+            match self.parser.riff_at(next) {
+                Ok(avix) if avix.id() == tag::AVIX => riff = avix,
+                _ => break,
+            }
+        }
+        Ok(report)
+    }
+
+    fn repair_list<W: Write + Seek>(
+        &self,
+        list: Riff<List>,
+        total_frames: u32,
+        writer: &mut W,
+        report: &mut RepairReport,
+    ) -> RiffResult<()> {
+        let start = writer.stream_position()?;
+        let endian = self.parser.endian();
+        HeaderType::list(list.id(), 0).write_endian(writer, endian)?;
+        for item in self.parser.chunks(list) {
+            match item? {
+                RiffType::List(list) => self.repair_list(list, total_frames, writer, report)?,
+                RiffType::Chunk(chunk) => self.repair_chunk(chunk, total_frames, writer, report)?,
+            }
+        }
+        if patch_list_size(writer, start, list, endian)? {
+            report.list_sizes_corrected += 1;
+        }
+        Ok(())
+    }
+
+    fn repair_chunk<W: Write + Seek>(
+        &self,
+        chunk: Riff<Chunk>,
+        total_frames: u32,
+        writer: &mut W,
+        report: &mut RepairReport,
+    ) -> RiffResult<()> {
+        let mut data = self.parser.read_data_vec(chunk)?;
+        if chunk.id() == tag::AVIH && data.len() >= 20 {
+            let flags = u32::from_le_bytes([data[12], data[13], data[14], data[15]])
+                | AviMainHeader::FLAG_HAS_INDEX;
+            data[12..16].copy_from_slice(&flags.to_le_bytes());
+            data[16..20].copy_from_slice(&total_frames.to_le_bytes());
+        }
+        HeaderType::chunk(chunk.id(), data.len() as u32)
+            .write_endian(writer, self.parser.endian())?;
+        writer.write_all(&data)?;
+        if !data.len().is_multiple_of(2) {
+            writer.write_all(&[0])?;
+            report.padded_chunks += 1;
+        }
+        Ok(())
+    }
+
+    /// Measure how far apart consecutive `movi` chunks of each stream are. The file is
+    /// considered well interleaved when no audio or video gap exceeds `max_gap_bytes`.
+    pub fn check_interleaving(&self, max_gap_bytes: u64) -> RiffResult<InterleavingReport> {
+        let mut chunk_counts = vec![0; self.stream_info.len()];
+        let mut last_end: Vec<Option<u64>> = vec![None; self.stream_info.len()];
+        let mut max_video_gap_bytes = 0;
+        let mut max_audio_gap_bytes = 0;
+
+        for result in self.iter_all_chunks() {
+            let (id, chunk) = result?;
+            let Some(index) = id
+                .stream_index()
+                .map(|index| index as usize)
+                .filter(|&index| index < self.stream_info.len())
+            else {
+                continue;
+            };
+            chunk_counts[index] += 1;
+            // Measured from the end of the previous chunk to the header of this one
+            let start = chunk.header_position();
+            if let Some(end) = last_end[index].replace(chunk.end_position()) {
+                let gap = start.saturating_sub(end);
+                match self.stream_info[index] {
+                    StreamInfo::Video(_) => max_video_gap_bytes = max_video_gap_bytes.max(gap),
+                    StreamInfo::Audio(_) => max_audio_gap_bytes = max_audio_gap_bytes.max(gap),
+                    StreamInfo::Text(_) | StreamInfo::Other(_) => {}
+                }
+            }
+        }
+
+        Ok(InterleavingReport {
+            chunk_counts,
+            max_video_gap_bytes,
+            max_audio_gap_bytes,
+            well_interleaved: max_video_gap_bytes <= max_gap_bytes
+                && max_audio_gap_bytes <= max_gap_bytes,
         })
     }
 
+    /// Average bitrate of the whole file over its duration
+    pub fn average_bitrate_bps(&self) -> Option<u64> {
+        let duration_us =
+            self.true_total_frames() as u64 * self.avi_header.micro_sec_per_frame as u64;
+        if duration_us == 0 {
+            return None;
+        }
+        let file_size = self.parser.stream_len().ok()?;
+        u64::try_from(file_size as u128 * 8 * 1_000_000 / duration_us as u128).ok()
+    }
+
+    /// Total data size of the `movi` list, including those of any `RIFF AVIX` segments
+    pub fn movi_size_bytes(&self) -> u64 {
+        self.extended_movi
+            .iter()
+            .fold(self.movi.data_size() as u64, |size, movi| {
+                size + movi.data_size() as u64
+            })
+    }
+
+    /// Size of everything in the file before the `LIST movi` header
+    pub fn header_size_bytes(&self) -> u64 {
+        self.movi.header_position()
+    }
+
+    pub fn audio_sample_count(&self) -> Option<u64> {
+        self.find_best_stream::<AudioStream>()
+            .map(AudioStream::total_samples)
+    }
+
+    /// Audio preroll of interleaved files, `initial_frames * micro_sec_per_frame` from `avih`.
+    /// `None` when the frame rate is unknown.
+    pub fn audio_preroll_duration(&self) -> Option<core::time::Duration> {
+        let header = &self.avi_header;
+        if header.micro_sec_per_frame == 0 {
+            return None;
+        }
+        Some(core::time::Duration::from_micros(
+            header.initial_frames as u64 * header.micro_sec_per_frame as u64,
+        ))
+    }
+
+    pub fn bitrate(&self) -> Option<Bitrate> {
+        self.average_bitrate_bps().map(Bitrate::from)
+    }
+
+    pub fn stream_count(&self) -> usize {
+        self.stream_info.len()
+    }
+
+    /// The streams in `hdrl` order, the index of a stream is its `movi` chunk id prefix
+    pub fn streams(&self) -> impl Iterator<Item = &StreamInfo> {
+        self.stream_info.iter()
+    }
+
+    /// The `StreamInfo` of the stream numbered `index` in its chunk ids, with `stream_count`
+    /// for generic code that doesn't know the stream types in advance
+    pub fn stream_at(&self, index: usize) -> Option<&StreamInfo> {
+        self.stream_info.get(index)
+    }
+
+    /// Pixel format of the best video stream
+    pub fn pixel_format(&self) -> Option<PixelFormat> {
+        self.find_best_stream::<VideoStream>()
+            .map(|stream| stream.bitmap_info.pixel_format())
+    }
+
+    /// `(width, height)` of the best video stream. Top down bitmaps have a negative height
+    /// in `BitmapInfo`, the absolute size is returned.
+    pub fn video_resolution(&self) -> Option<(u32, u32)> {
+        self.find_best_stream::<VideoStream>()
+            .map(|stream| resolution(&stream.bitmap_info))
+    }
+
+    /// `(width, height)` of every video stream in `hdrl` order
+    pub fn video_resolution_all(&self) -> Vec<(u32, u32)> {
+        self.stream_info
+            .iter()
+            .filter_map(|stream| match stream {
+                StreamInfo::Video(video) => Some(resolution(&video.bitmap_info)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Display aspect ratio of the best video stream. The `vprp` frame aspect ratio is used
+    /// when present, so anamorphic video such as 720x480 shown at 4:3 is reported as 4:3,
+    /// otherwise square pixels are assumed and the ratio of the pixel dimensions is returned.
+    pub fn display_aspect_ratio(&self) -> Option<AspectRatio> {
+        let stream = self.find_best_stream::<VideoStream>()?;
+        stream
+            .video_properties
+            .as_ref()
+            .and_then(|properties| {
+                let FrameAspectRatio { x, y } = properties.frame_aspect_ratio;
+                AspectRatio::new(x.into(), y.into())
+            })
+            .or_else(|| {
+                let (width, height) = resolution(&stream.bitmap_info);
+                AspectRatio::new(width, height)
+            })
+    }
+
+    /// The stream of type `S` with the highest `priority`, the last of any ties
     pub fn find_best_stream<S>(&self) -> Option<&S>
+    where
+        for<'a> &'a S: TryFrom<&'a StreamInfo, Error = ()>,
+        S: Stream,
+    {
+        self.find_best_stream_with_policy(StreamSelectionPolicy::HighestPriority)
+    }
+
+    /// The stream of type `S` with the highest `priority`, ties broken by `policy`
+    pub fn find_best_stream_with_policy<S>(&self, policy: StreamSelectionPolicy) -> Option<&S>
     where
         for<'a> &'a S: TryFrom<&'a StreamInfo, Error = ()>,
         S: Stream,
     {
         self.stream_info
             .iter()
-            .filter_map(|stream| <&S>::try_from(stream).ok())
-            .max_by_key(|&stream| stream.stream_header().priority)
+            .filter_map(|info| <&S>::try_from(info).ok().map(|stream| (info, stream)))
+            .max_by_key(|&(info, stream)| {
+                let tie_break = match policy {
+                    StreamSelectionPolicy::HighestPriority => 0,
+                    StreamSelectionPolicy::BestQuality => match info {
+                        StreamInfo::Video(video) => {
+                            let (width, height) = resolution(&video.bitmap_info);
+                            width as u64 * height as u64
+                        }
+                        _ => info.average_bitrate_bps().unwrap_or(0),
+                    },
+                    StreamSelectionPolicy::PreferCodec(codec) => {
+                        let compression = match info {
+                            StreamInfo::Video(video) => Some(video.bitmap_info.compression),
+                            _ => None,
+                        };
+                        (stream.stream_header().fcc_handler == codec
+                            || compression == Some(u32::from(codec))) as u64
+                    }
+                };
+                (stream.stream_header().priority, tie_break)
+            })
+            .map(|(_, stream)| stream)
+    }
+
+    /// The `txts` streams in `hdrl` order
+    pub fn subtitle_streams(&self) -> impl Iterator<Item = &TextStream> {
+        self.stream_info
+            .iter()
+            .filter_map(|stream| <&TextStream>::try_from(stream).ok())
+    }
+
+    /// Streams grouped by `stream_header.fcc_type`, each group in `hdrl` order
+    pub fn streams_by_type(&self) -> FourccMap<Vec<&StreamInfo>> {
+        let mut map: FourccMap<Vec<&StreamInfo>> = FourccMap::new();
+        for stream in &self.stream_info {
+            let fcc_type = stream.stream_header().fcc_type;
+            match map.get_mut(fcc_type) {
+                Some(streams) => streams.push(stream),
+                None => {
+                    map.insert(fcc_type, vec![stream]);
+                }
+            }
+        }
+        map
+    }
+
+    pub fn find_stream_by_type(&self, fcc_type: Fourcc) -> Option<&StreamInfo> {
+        self.stream_info
+            .iter()
+            .find(|stream| stream.stream_header().fcc_type == fcc_type)
     }
 
     pub fn stream_chunks(
         &self,
         stream_id: Fourcc,
         movi: Riff<List>,
-    ) -> impl Iterator<Item = Result<Riff<Chunk>, Error>> + '_ {
-        self.parser.chunks(movi).filter_map(move |result| {
-            if let Ok(RiffType::Chunk(chunk)) = result
-                && chunk.id() == stream_id
-            {
-                Some(Ok(chunk))
-            } else {
-                None
-            }
-        })
+    ) -> impl Iterator<Item = RiffResult<Riff<Chunk>>> + '_ {
+        self.movi_iter(movi)
+            .filter(move |result| !matches!(result, Ok(chunk) if chunk.id() != stream_id))
+    }
+
+    fn movi_iter(&self, movi: Riff<List>) -> MoviIter<'_, R> {
+        MoviIter {
+            parser: &self.parser,
+            movi: self.parser.chunks(movi),
+            rec: None,
+        }
     }
 
     pub fn movi_chunks(
         &self,
         stream_id: Fourcc,
-    ) -> impl Iterator<Item = Result<Riff<Chunk>, Error>> + '_ {
-        self.stream_chunks(stream_id, self.movi)
+    ) -> impl Iterator<Item = RiffResult<Riff<Chunk>>> + '_ {
+        self.stream_chunks(stream_id, self.movi).chain(
+            self.extended_movi
+                .iter()
+                .flat_map(move |&movi| self.stream_chunks(stream_id, movi)),
+        )
+    }
+
+    /// The palette changes of the video stream `stream_index` in `movi` order,
+    /// from its `##pc` chunks
+    pub fn palette_changes(
+        &self,
+        stream_index: u32,
+    ) -> impl Iterator<Item = RiffResult<PaletteChange>> + '_ {
+        self.movi_chunks(tag::stream(stream_index, tag::DATA_PALETTE_CHANGED))
+            .map(|chunk| self.parser.read_data_struct(chunk?))
+    }
+
+    /// Chapters from the points of a `cue ` chunk, named by the `labl` chunks of a
+    /// `LIST adtl`, found at the top level of the `RIFF AVI ` or in `hdrl`, in time order.
+    /// This is the RIFF cue convention of WAVE files, AVI has no chapter format of its own.
+    /// Cue positions are frame numbers. A file without a `cue ` chunk has no marks, read
+    /// errors are returned.
+    pub fn chapter_marks(&self) -> RiffResult<Vec<ChapterMark>> {
+        let mut cue = None;
+        let mut labels = Vec::new();
+        let mut lists = vec![self.riff];
+        while let Some(list) = lists.pop() {
+            for result in self.parser.chunks(list) {
+                match result? {
+                    RiffType::List(hdrl) if hdrl.id() == tag::HDRL => lists.push(hdrl),
+                    RiffType::List(adtl) if adtl.id() == crate::tag::ADTL => {
+                        for result in self.parser.chunks(adtl) {
+                            if let RiffType::Chunk(labl) = result?
+                                && labl.id() == crate::tag::LABL
+                            {
+                                let data = self.parser.read_data_vec(labl)?;
+                                if let Some((id, text)) = data.split_first_chunk::<4>() {
+                                    labels.push((u32::from_le_bytes(*id), text_until_null(text)));
+                                }
+                            }
+                        }
+                    }
+                    RiffType::Chunk(chunk) if chunk.id() == crate::tag::CUE => {
+                        cue = Some(self.parser.read_data_struct::<CueList>(chunk)?);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let Some(cue) = cue else {
+            return Ok(Vec::new());
+        };
+        let mut marks: Vec<_> = cue
+            .points
+            .iter()
+            .map(|point| ChapterMark {
+                time_us: u64::from(point.position) * u64::from(self.avi_header.micro_sec_per_frame),
+                name: labels
+                    .iter()
+                    .find(|(id, _)| *id == point.id)
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        marks.sort_by_key(|mark| mark.time_us);
+        Ok(marks)
+    }
+
+    /// Every chunk of `movi` and any OpenDML `movi` lists in file order with its id,
+    /// descending into `LIST rec ` groups. Since the groups are descended into, every item
+    /// is a chunk, so it's a `Riff<Chunk>` rather than a `RiffType` to match on.
+    pub fn iter_all_chunks(&self) -> impl Iterator<Item = RiffResult<(Fourcc, Riff<Chunk>)>> + '_ {
+        core::iter::once(self.movi)
+            .chain(self.extended_movi.iter().copied())
+            .flat_map(|movi| self.movi_iter(movi))
+            .map(|result| result.map(|chunk| (chunk.id(), chunk)))
+    }
+
+    /// Read the data of the first `movi` chunk for `stream_id`,
+    /// located via `idx1` when present instead of scanning `movi`
+    pub fn first_frame(&self, stream_id: Fourcc) -> RiffResult<Vec<u8>> {
+        if let Some(chunk) = self.first_indexed_chunk(stream_id)? {
+            return self.parser.read_data_vec(chunk);
+        }
+        let chunk = self
+            .movi_chunks(stream_id)
+            .next()
+            .ok_or_else(|| missing_error(self.movi.position(), stream_id))??;
+        self.parser.read_data_vec(chunk)
+    }
+
+    /// The first frame of the best video stream, if there is one
+    pub fn thumbnail(&self) -> RiffResult<Option<Vec<u8>>> {
+        self.find_best_stream::<VideoStream>()
+            .map(|stream| self.first_frame(stream.stream_id))
+            .transpose()
+    }
+
+    fn first_indexed_chunk(&self, stream_id: Fourcc) -> RiffResult<Option<Riff<Chunk>>> {
+        let Some(idx1) = self.idx1 else {
+            return Ok(None);
+        };
+        // idx1 offsets are usually relative to the movi list id, but some writers use
+        // absolute offsets, decide which based on the first entry
+        let movi_start = self.movi.position() - size_of::<Fourcc>() as u64;
+        let entry_size = size_of::<AviIndexEntry>() as u32;
+        let mut base = None;
+        let mut offset = 0;
+        while offset + entry_size <= idx1.data_size() {
+            let entry = self
+                .parser
+                .read_data_struct_at::<AviIndexEntry>(idx1, offset)?;
+            offset += entry_size;
+            let base = *base.get_or_insert(if entry.offset as u64 >= movi_start {
+                0
+            } else {
+                movi_start
+            });
+            if entry.chunk_id != stream_id {
+                continue;
+            }
+            return match self.parser.read_header_at(base + entry.offset as u64) {
+                Ok(RiffType::Chunk(chunk)) if chunk.id() == stream_id => Ok(Some(chunk)),
+                _ => Ok(None),
+            };
+        }
+        Ok(None)
     }
 
     pub fn riff_parser(&self) -> &RiffParser<R> {
         &self.parser
     }
+}
 
-    fn eof_error() -> Error {
-        Error::Io(io::Error::from(io::ErrorKind::UnexpectedEof))
+#[cfg(feature = "std")]
+impl AviParser<binrw::io::BufReader<std::fs::File>> {
+    /// Open and parse the AVI file at `path`
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> RiffResult<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::new(RiffParser::new(binrw::io::BufReader::new(file)))
     }
+}
+
+/// Parse a header struct from its chunk, checking the chunk id,
+/// e.g. `AviMainHeader::try_from((&parser, avih))?`
+macro_rules! impl_try_from_chunk {
+    ($($type:ty => $tag:expr),* $(,)?) => {
+        $(
+            impl<'a, R: Read + Seek> TryFrom<(&'a RiffParser<R>, Riff<Chunk>)> for $type {
+                type Error = RiffError;
+
+                fn try_from(
+                    (parser, chunk): (&'a RiffParser<R>, Riff<Chunk>),
+                ) -> RiffResult<Self> {
+                    validate_tag(&chunk, $tag)?;
+                    parser.read_data_struct(chunk)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_chunk! {
+    AviMainHeader => tag::AVIH,
+    AviStreamHeader => tag::STRH,
+    BitmapInfo => tag::STRF,
+    WaveFormat => tag::STRF,
+}
+
+/// Write the size of the list whose header was written at `start`, now that its items are
+/// written. Returns whether it differs from the size of `list` it was copied from.
+fn patch_list_size<W: Write + Seek>(
+    writer: &mut W,
+    start: u64,
+    list: Riff<List>,
+    endian: Endian,
+) -> RiffResult<bool> {
+    let end = writer.stream_position()?;
+    let size = (end - start - 8) as u32;
+    writer.seek(SeekFrom::Start(start + 4))?;
+    let bytes = match endian {
+        Endian::Big => size.to_be_bytes(),
+        Endian::Little => size.to_le_bytes(),
+    };
+    writer.write_all(&bytes)?;
+    writer.seek(SeekFrom::Start(end))?;
+    Ok(size != list.data_size() + size_of::<Fourcc>() as u32)
+}
+
+fn resolution(bitmap_info: &BitmapInfo) -> (u32, u32) {
+    (
+        bitmap_info.width.unsigned_abs(),
+        bitmap_info.height.unsigned_abs(),
+    )
+}
+
+/// Text up to the first null byte, decoded lossily
+fn text_until_null(data: &[u8]) -> String {
+    let text = data.split(|&b| b == 0).next().unwrap_or_default();
+    String::from_utf8_lossy(text).into_owned()
+}
 
-    fn missing_error(position: u64, tag: Fourcc) -> Error {
-        Error::AssertFail {
-            pos: position,
-            message: format!("missing {}", tag),
+/// Stream index from the two digit prefix of a `movi` chunk id
+fn stream_index(digits: [u8; 2]) -> Option<usize> {
+    match digits {
+        [tens @ b'0'..=b'9', ones @ b'0'..=b'9'] => {
+            Some(((tens - b'0') * 10 + (ones - b'0')) as usize)
         }
+        _ => None,
     }
+}
 
-    fn validate_tag<H: Header>(riff: &Riff<H>, tag: Fourcc) -> Result<(), Error> {
-        if riff.id() != tag {
-            Err(Self::missing_error(riff.position(), tag))
-        } else {
-            Ok(())
+/// Iterates the chunks of a `movi` list, descending into `LIST rec ` groups
+struct MoviIter<'a, R> {
+    parser: &'a RiffParser<R>,
+    movi: ListIter<R>,
+    rec: Option<ListIter<R>>,
+}
+
+impl<R: Read + Seek> Iterator for MoviIter<'_, R> {
+    type Item = RiffResult<Riff<Chunk>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(rec) = &mut self.rec {
+                match rec.next() {
+                    Some(Ok(RiffType::Chunk(chunk))) => return Some(Ok(chunk)),
+                    Some(Ok(RiffType::List(_))) => continue,
+                    Some(Err(e)) => return Some(Err(e)),
+                    None => self.rec = None,
+                }
+            }
+            match self.movi.next()? {
+                Ok(RiffType::Chunk(chunk)) => return Some(Ok(chunk)),
+                Ok(RiffType::List(list)) if list.id() == tag::REC => {
+                    self.rec = Some(self.parser.chunks(list))
+                }
+                Ok(RiffType::List(_)) => {}
+                Err(e) => return Some(Err(e)),
+            }
         }
     }
 }
 
 impl<R: Read + Seek> Debug for AviParser<R> {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("AviParser")
             .field("stream_info", &self.stream_info)
             .finish()
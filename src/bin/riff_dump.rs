@@ -0,0 +1,192 @@
+//! Print the lists and chunks of a RIFF file as an indented tree, or as JSON with `--json`.
+//! AVI and WAVE header chunks are decoded.
+//!
+//! ```text
+//! riff-dump [--json] <path>
+//! ```
+
+use riffparse::{
+    BufReader, Chunk, List, Read, Riff, RiffError, RiffParser, RiffResult, Seek, WalkControl,
+    WalkEvent, avi,
+    fourcc::Fourcc,
+    tag::{FMT, WAVE},
+};
+use serde_json::{Value, json};
+use std::{fs::File, process::ExitCode};
+
+#[derive(Debug)]
+enum Decoded {
+    MainHeader(avi::AviMainHeader),
+    StreamHeader(avi::AviStreamHeader),
+    BitmapInfo(avi::BitmapInfo),
+    WaveFormat(avi::WaveFormat),
+}
+
+impl Decoded {
+    fn to_json(&self) -> Value {
+        match self {
+            Decoded::MainHeader(header) => json!(header),
+            Decoded::StreamHeader(header) => json!(header),
+            Decoded::BitmapInfo(info) => json!(info),
+            Decoded::WaveFormat(format) => json!(format),
+        }
+    }
+}
+
+/// Decodes the chunks it knows, remembering the type of the last `strh` for its `strf`
+struct Decoder {
+    form_type: Fourcc,
+    stream_type: Option<Fourcc>,
+}
+
+impl Decoder {
+    fn decode<R: Read + Seek>(
+        &mut self,
+        parser: &RiffParser<R>,
+        chunk: Riff<Chunk>,
+    ) -> RiffResult<Option<Decoded>> {
+        let decoded = match (self.form_type, chunk.id()) {
+            (avi::tag::AVI, avi::tag::AVIH) => Decoded::MainHeader(parser.read_data_struct(chunk)?),
+            (avi::tag::AVI, avi::tag::STRH) => {
+                let header = parser.read_data_struct::<avi::AviStreamHeader>(chunk)?;
+                self.stream_type = Some(header.fcc_type);
+                Decoded::StreamHeader(header)
+            }
+            (avi::tag::AVI, avi::tag::STRF) => match self.stream_type.take() {
+                Some(avi::tag::VIDS) => Decoded::BitmapInfo(parser.read_data_struct(chunk)?),
+                Some(avi::tag::AUDS) => Decoded::WaveFormat(parser.read_data_struct(chunk)?),
+                _ => return Ok(None),
+            },
+            (WAVE, FMT) => Decoded::WaveFormat(parser.read_data_struct(chunk)?),
+            _ => return Ok(None),
+        };
+        Ok(Some(decoded))
+    }
+}
+
+fn list_json(list: Riff<List>, kind: &str) -> Value {
+    json!({
+        "kind": kind,
+        "id": list.id(),
+        "position": list.header_position(),
+        "size": list.data_size(),
+        "items": [],
+    })
+}
+
+fn dump<R: Read + Seek>(parser: &RiffParser<R>, as_json: bool) -> RiffResult<()> {
+    let mut decoder = Decoder {
+        form_type: parser.riff()?.id(),
+        stream_type: None,
+    };
+    let mut error = None;
+    let mut depth = 0;
+    // JSON of the lists being walked, outermost first
+    let mut lists: Vec<Value> = Vec::new();
+    let mut root = Value::Null;
+
+    parser.depth_first_walk(|event| {
+        match event {
+            WalkEvent::Enter(list) => {
+                let kind = if depth == 0 { "RIFF" } else { "LIST" };
+                if as_json {
+                    lists.push(list_json(list, kind));
+                } else {
+                    println!(
+                        "{:indent$}{kind} '{}' @{} size {}",
+                        "",
+                        list.id(),
+                        list.header_position(),
+                        list.data_size(),
+                        indent = depth * 4
+                    );
+                }
+                depth += 1;
+            }
+            WalkEvent::Exit(_) => {
+                depth -= 1;
+                if as_json {
+                    let list = lists.pop().expect("exit follows enter");
+                    match lists.last_mut() {
+                        Some(parent) => parent["items"].as_array_mut().unwrap().push(list),
+                        None => root = list,
+                    }
+                }
+            }
+            WalkEvent::Chunk(chunk) => {
+                let decoded = match decoder.decode(parser, chunk) {
+                    Ok(decoded) => decoded,
+                    Err(e) => {
+                        error = Some(e);
+                        return WalkControl::Abort;
+                    }
+                };
+                if as_json {
+                    let mut value = json!({
+                        "kind": "chunk",
+                        "id": chunk.id(),
+                        "position": chunk.header_position(),
+                        "size": chunk.data_size(),
+                    });
+                    if let Some(decoded) = decoded {
+                        value["fields"] = decoded.to_json();
+                    }
+                    let parent = lists.last_mut().expect("chunks are inside the RIFF list");
+                    parent["items"].as_array_mut().unwrap().push(value);
+                } else {
+                    println!(
+                        "{:indent$}'{}' @{} size {}",
+                        "",
+                        chunk.id(),
+                        chunk.header_position(),
+                        chunk.data_size(),
+                        indent = depth * 4
+                    );
+                    if let Some(decoded) = decoded {
+                        println!("{:indent$}{decoded:?}", "", indent = (depth + 1) * 4);
+                    }
+                }
+            }
+        }
+        WalkControl::Continue
+    })?;
+    if let Some(e) = error {
+        return Err(e);
+    }
+    if as_json {
+        let output = serde_json::to_string_pretty(&root).map_err(|e| RiffError::Io(e.into()))?;
+        println!("{output}");
+    }
+    Ok(())
+}
+
+fn usage() -> ExitCode {
+    eprintln!("usage: riff-dump [--json] <path>");
+    ExitCode::FAILURE
+}
+
+fn main() -> ExitCode {
+    let mut as_json = false;
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => as_json = true,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return usage(),
+        }
+    }
+    let Some(path) = path else {
+        return usage();
+    };
+
+    let result = File::open(&path)
+        .map_err(RiffError::from)
+        .and_then(|file| dump(&RiffParser::new(BufReader::new(file)), as_json));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{HeaderType, RiffError, RiffResult, fourcc::Fourcc};
+use alloc::{format, vec::Vec};
+use binrw::io::Write;
+
+#[derive(Debug, Clone)]
+enum Item {
+    Chunk(Fourcc, Vec<u8>),
+    List(Fourcc, ListBuilder),
+}
+
+impl Item {
+    /// Bytes written for the item, including its header and pad byte
+    fn total_size(&self) -> u64 {
+        match self {
+            Item::Chunk(_, data) => 8 + padded(data.len() as u64),
+            Item::List(_, list) => 12 + list.data_size(),
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> RiffResult<()> {
+        match self {
+            Item::Chunk(id, data) => {
+                HeaderType::chunk(*id, data.len() as u32).write(writer)?;
+                writer.write_all(data)?;
+                if !data.len().is_multiple_of(2) {
+                    writer.write_all(&[0])?;
+                }
+                Ok(())
+            }
+            Item::List(list_type, list) => {
+                HeaderType::list(*list_type, (4 + list.data_size()) as u32).write(writer)?;
+                list.write_items(writer)
+            }
+        }
+    }
+}
+
+/// Children of a `LIST`, added to a `RiffBuilder` or another `ListBuilder` with `add_list`
+#[derive(Debug, Clone, Default)]
+pub struct ListBuilder {
+    items: Vec<Item>,
+}
+
+impl ListBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a chunk, odd sized data is padded when written
+    pub fn add_chunk(&mut self, id: Fourcc, data: &[u8]) -> &mut Self {
+        self.items.push(Item::Chunk(id, data.to_vec()));
+        self
+    }
+
+    /// Append `LIST list_type` holding the items of `builder`
+    pub fn add_list(&mut self, list_type: Fourcc, builder: ListBuilder) -> &mut Self {
+        self.items.push(Item::List(list_type, builder));
+        self
+    }
+
+    /// Size of the items, excluding the list header and type
+    fn data_size(&self) -> u64 {
+        self.items.iter().map(Item::total_size).sum()
+    }
+
+    fn write_items<W: Write>(&self, writer: &mut W) -> RiffResult<()> {
+        self.items.iter().try_for_each(|item| item.write(writer))
+    }
+}
+
+/// Builds a `RIFF` file in memory, computing chunk sizes and padding when written
+#[derive(Debug, Clone)]
+pub struct RiffBuilder {
+    form_type: Fourcc,
+    items: ListBuilder,
+}
+
+impl RiffBuilder {
+    pub fn new(form_type: Fourcc) -> Self {
+        Self {
+            form_type,
+            items: ListBuilder::new(),
+        }
+    }
+
+    /// Append a chunk, odd sized data is padded when written
+    pub fn add_chunk(&mut self, id: Fourcc, data: &[u8]) -> &mut Self {
+        self.items.add_chunk(id, data);
+        self
+    }
+
+    /// Append `LIST list_type` holding the items of `builder`
+    pub fn add_list(&mut self, list_type: Fourcc, builder: ListBuilder) -> &mut Self {
+        self.items.add_list(list_type, builder);
+        self
+    }
+
+    /// Bytes `build` will write
+    pub fn total_size(&self) -> u64 {
+        12 + self.items.data_size()
+    }
+
+    /// Write the `RIFF` header followed by every item.
+    /// Fails without writing anything if the file would exceed the 4GB RIFF limit.
+    pub fn build<W: Write>(&self, writer: &mut W) -> RiffResult<()> {
+        let size = 4 + self.items.data_size();
+        if size > u32::MAX as u64 {
+            return Err(RiffError::MalformedChunk {
+                position: 0,
+                message: format!("RIFF size {size} exceeds 4GB"),
+            });
+        }
+        HeaderType::riff(self.form_type, size as u32).write(writer)?;
+        self.items.write_items(writer)
+    }
+}
+
+fn padded(size: u64) -> u64 {
+    size + (size & 1)
+}
@@ -0,0 +1,151 @@
+use crate::fourcc::{Fourcc, FourccMap};
+use alloc::vec::Vec;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CodecDescriptor {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub lossy: bool,
+}
+
+impl CodecDescriptor {
+    pub const fn new(name: &'static str, description: &'static str, lossy: bool) -> Self {
+        Self {
+            name,
+            description,
+            lossy,
+        }
+    }
+}
+
+const H264: CodecDescriptor = CodecDescriptor::new("H.264", "MPEG-4 AVC", true);
+const HEVC: CodecDescriptor =
+    CodecDescriptor::new("HEVC", "H.265 High Efficiency Video Coding", true);
+const MJPEG: CodecDescriptor = CodecDescriptor::new("MJPEG", "Motion JPEG", true);
+const DIVX: CodecDescriptor = CodecDescriptor::new("DivX", "DivX MPEG-4 Part 2", true);
+const XVID: CodecDescriptor = CodecDescriptor::new("Xvid", "Xvid MPEG-4 Part 2", true);
+
+/// Video codecs by `BitmapInfo::compression` or `AviStreamHeader::fcc_handler`.
+/// Writers disagree on case, so common variants are listed separately.
+const VIDEO: &[([u8; 4], CodecDescriptor)] = &[
+    (*b"H264", H264),
+    (*b"h264", H264),
+    (*b"X264", H264),
+    (*b"x264", H264),
+    (*b"AVC1", H264),
+    (*b"avc1", H264),
+    (*b"HEVC", HEVC),
+    (*b"hevc", HEVC),
+    (*b"H265", HEVC),
+    (*b"h265", HEVC),
+    (*b"HEVX", HEVC),
+    (*b"hev1", HEVC),
+    (*b"hvc1", HEVC),
+    (*b"MJPG", MJPEG),
+    (*b"mjpg", MJPEG),
+    (*b"DIVX", DIVX),
+    (*b"divx", DIVX),
+    (*b"DX50", DIVX),
+    (*b"XVID", XVID),
+    (*b"xvid", XVID),
+    (*b"VP80", CodecDescriptor::new("VP8", "WebM VP8", true)),
+    (*b"VP90", CodecDescriptor::new("VP9", "WebM VP9", true)),
+    (
+        *b"AV01",
+        CodecDescriptor::new("AV1", "AOMedia Video 1", true),
+    ),
+];
+
+const VORBIS: CodecDescriptor = CodecDescriptor::new("Vorbis", "Ogg Vorbis", true);
+
+/// Audio codecs by the `wFormatTag` that starts a `WaveFormat`
+const AUDIO: &[(u16, CodecDescriptor)] = &[
+    (0x0001, CodecDescriptor::new("PCM", "Linear PCM", false)),
+    (
+        0x0003,
+        CodecDescriptor::new("PCM float", "IEEE floating point PCM", false),
+    ),
+    (
+        0x0050,
+        CodecDescriptor::new("MP2", "MPEG-1 Audio Layer I/II", true),
+    ),
+    (
+        0x0055,
+        CodecDescriptor::new("MP3", "MPEG-1 Audio Layer III", true),
+    ),
+    (
+        0x00ff,
+        CodecDescriptor::new("AAC", "Advanced Audio Coding", true),
+    ),
+    (
+        0x1610,
+        CodecDescriptor::new("AAC", "Advanced Audio Coding, ADTS", true),
+    ),
+    (0x2000, CodecDescriptor::new("AC-3", "Dolby Digital", true)),
+    (
+        0x2001,
+        CodecDescriptor::new("DTS", "DTS Coherent Acoustics", true),
+    ),
+    (0x674f, VORBIS),
+    (0x6750, VORBIS),
+    (0x6751, VORBIS),
+    (0x704f, CodecDescriptor::new("Opus", "Opus", true)),
+];
+
+/// Names for codec fourccs and audio format tags.
+/// The default registry knows common codecs, more can be registered.
+#[derive(Debug, Clone)]
+pub struct CodecRegistry {
+    video: FourccMap<CodecDescriptor>,
+    audio: Vec<(u16, CodecDescriptor)>,
+}
+
+impl CodecRegistry {
+    /// A registry without any codecs
+    pub fn empty() -> Self {
+        Self {
+            video: FourccMap::new(),
+            audio: Vec::new(),
+        }
+    }
+
+    pub fn describe_video(&self, fourcc: Fourcc) -> Option<&CodecDescriptor> {
+        self.video.get(fourcc)
+    }
+
+    pub fn describe_audio(&self, tag: u16) -> Option<&CodecDescriptor> {
+        self.audio
+            .iter()
+            .find(|(format_tag, _)| *format_tag == tag)
+            .map(|(_, descriptor)| descriptor)
+    }
+
+    /// Add or replace the descriptor for a video fourcc
+    pub fn register_video(&mut self, fourcc: Fourcc, descriptor: CodecDescriptor) {
+        self.video.insert(fourcc, descriptor);
+    }
+
+    /// Add or replace the descriptor for an audio format tag
+    pub fn register_audio(&mut self, tag: u16, descriptor: CodecDescriptor) {
+        match self
+            .audio
+            .iter_mut()
+            .find(|(format_tag, _)| *format_tag == tag)
+        {
+            Some((_, existing)) => *existing = descriptor,
+            None => self.audio.push((tag, descriptor)),
+        }
+    }
+}
+
+impl Default for CodecRegistry {
+    fn default() -> Self {
+        Self {
+            video: VIDEO
+                .iter()
+                .map(|&(fourcc, descriptor)| (Fourcc::new(fourcc), descriptor))
+                .collect(),
+            audio: AUDIO.to_vec(),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    Chunk, List, Riff, RiffError, RiffParser, RiffResult, RiffType, error::ErrorContext,
+    fourcc::Fourcc, riff::Header,
+};
+use alloc::{string::String, vec::Vec};
+use binrw::{
+    BinRead,
+    io::{Read, Seek},
+};
+
+pub type ContextResult<T> = Result<T, ErrorContext<RiffError>>;
+
+impl<R: Read + Seek> RiffParser<R> {
+    /// Attach `path` to every error of the returned parser, e.g. when reporting
+    /// failures across many files
+    pub fn with_context(self, path: impl Into<String>) -> ContextualParser<R> {
+        ContextualParser {
+            parser: self,
+            path: path.into(),
+        }
+    }
+}
+
+/// Wraps the `RiffParser` methods to return `ErrorContext` errors holding the path and the
+/// offset of the failure. The offset is the position of a `MalformedChunk` error, otherwise
+/// where the failing read started. Use `parser` for methods that aren't wrapped.
+pub struct ContextualParser<R> {
+    parser: RiffParser<R>,
+    path: String,
+}
+
+impl<R: Read + Seek> ContextualParser<R> {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn parser(&self) -> &RiffParser<R> {
+        &self.parser
+    }
+
+    pub fn into_inner(self) -> RiffParser<R> {
+        self.parser
+    }
+
+    /// Add the path and `offset` to the error of `result`
+    pub fn context<T>(&self, result: RiffResult<T>, offset: u64) -> ContextResult<T> {
+        result.map_err(|inner| ErrorContext {
+            offset: match inner {
+                RiffError::MalformedChunk { position, .. } => position,
+                _ => offset,
+            },
+            inner,
+            path: Some(self.path.clone()),
+        })
+    }
+
+    pub fn riff(&self) -> ContextResult<Riff<List>> {
+        self.context(self.parser.riff(), 0)
+    }
+
+    pub fn riff_at(&self, position: u64) -> ContextResult<Riff<List>> {
+        self.context(self.parser.riff_at(position), position)
+    }
+
+    pub fn chunks(&self, list: Riff<List>) -> impl Iterator<Item = ContextResult<RiffType>> + '_ {
+        self.parser
+            .chunks(list)
+            .map(move |item| self.context(item, list.position()))
+    }
+
+    pub fn find_chunk(&self, list: Riff<List>, id: Fourcc) -> ContextResult<Option<Riff<Chunk>>> {
+        self.context(self.parser.find_chunk(list, id), list.position())
+    }
+
+    pub fn find_list(&self, list: Riff<List>, id: Fourcc) -> ContextResult<Option<Riff<List>>> {
+        self.context(self.parser.find_list(list, id), list.position())
+    }
+
+    pub fn read_data_struct<S>(&self, chunk: Riff<Chunk>) -> ContextResult<S>
+    where
+        S: BinRead + Sized,
+        for<'a> <S as BinRead>::Args<'a>: Default,
+    {
+        self.context(self.parser.read_data_struct(chunk), chunk.position())
+    }
+
+    pub fn read_data<H: Header>(&self, chunk: Riff<H>, buffer: &mut [u8]) -> ContextResult<()> {
+        self.context(self.parser.read_data(chunk, buffer), chunk.position())
+    }
+
+    pub fn read_data_vec<H: Header>(&self, chunk: Riff<H>) -> ContextResult<Vec<u8>> {
+        self.context(self.parser.read_data_vec(chunk), chunk.position())
+    }
+
+    pub fn read_data_string(&self, chunk: Riff<Chunk>) -> ContextResult<String> {
+        self.context(self.parser.read_data_string(chunk), chunk.position())
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    Chunk, List, Riff, RiffParser, RiffResult, RiffType,
+    fourcc::{Fourcc, FourccMap},
+};
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use binrw::io::{Read, Seek};
+
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Compare the data of chunks with the same size, reading every chunk of both files
+    pub compare_data: bool,
+}
+
+/// A structural difference between two files. `path` holds the ids from the `RIFF`
+/// form type down to the item, e.g. `AVI `, `hdrl`, `avih`.
+#[derive(Debug, Clone)]
+pub enum ChunkDiff {
+    /// `item` in the second file has no counterpart in the first
+    Added { path: Vec<Fourcc>, item: RiffType },
+    /// `item` in the first file has no counterpart in the second
+    Removed { path: Vec<Fourcc>, item: RiffType },
+    SizeChanged {
+        path: Vec<Fourcc>,
+        a: Riff<Chunk>,
+        b: Riff<Chunk>,
+    },
+    /// Only reported when `DiffOptions::compare_data` is set
+    DataChanged {
+        path: Vec<Fourcc>,
+        a: Riff<Chunk>,
+        b: Riff<Chunk>,
+    },
+}
+
+/// Compare the structure of two RIFF files.
+///
+/// Items of each list are matched by id and occurrence, so the third `00dc` chunk in `a`
+/// is compared with the third `00dc` chunk in `b`, and a chunk inserted in `b` is reported
+/// once as `Added` rather than shifting every following chunk. Matched lists are compared
+/// recursively. Differences are returned in the order of `a`, with the items added to
+/// each list following those of the list.
+pub fn diff_riff<R1: Read + Seek, R2: Read + Seek>(
+    a: R1,
+    b: R2,
+    options: &DiffOptions,
+) -> RiffResult<Vec<ChunkDiff>> {
+    let a = RiffParser::new(a);
+    let b = RiffParser::new(b);
+    let a_riff = a.riff()?;
+    let b_riff = b.riff()?;
+    let mut diffs = Vec::new();
+    if a_riff.id() != b_riff.id() {
+        diffs.push(ChunkDiff::Removed {
+            path: vec![a_riff.id()],
+            item: RiffType::List(a_riff),
+        });
+        diffs.push(ChunkDiff::Added {
+            path: vec![b_riff.id()],
+            item: RiffType::List(b_riff),
+        });
+        return Ok(diffs);
+    }
+    let mut differ = Differ {
+        a,
+        b,
+        options,
+        path: vec![a_riff.id()],
+        diffs,
+    };
+    differ.diff_list(a_riff, b_riff)?;
+    Ok(differ.diffs)
+}
+
+struct Differ<'a, R1, R2> {
+    a: RiffParser<R1>,
+    b: RiffParser<R2>,
+    options: &'a DiffOptions,
+    /// Ids of the lists enclosing the items being compared
+    path: Vec<Fourcc>,
+    diffs: Vec<ChunkDiff>,
+}
+
+impl<R1: Read + Seek, R2: Read + Seek> Differ<'_, R1, R2> {
+    fn item_path(&self, id: Fourcc) -> Vec<Fourcc> {
+        let mut path = self.path.clone();
+        path.push(id);
+        path
+    }
+
+    fn diff_list(&mut self, a_list: Riff<List>, b_list: Riff<List>) -> RiffResult<()> {
+        let a_items = self.a.chunks(a_list).collect::<RiffResult<Vec<_>>>()?;
+        let b_items = self.b.chunks(b_list).collect::<RiffResult<Vec<_>>>()?;
+
+        // Indices of the unmatched items of b, by id, for lists and chunks separately
+        let mut b_lists = FourccMap::<VecDeque<usize>>::new();
+        let mut b_chunks = FourccMap::<VecDeque<usize>>::new();
+        for (index, item) in b_items.iter().enumerate() {
+            let map = match item {
+                RiffType::List(_) => &mut b_lists,
+                RiffType::Chunk(_) => &mut b_chunks,
+            };
+            match map.get_mut(item.id()) {
+                Some(indices) => indices.push_back(index),
+                None => {
+                    map.insert(item.id(), VecDeque::from([index]));
+                }
+            }
+        }
+        let mut matched = vec![false; b_items.len()];
+
+        for a_item in a_items {
+            let map = match a_item {
+                RiffType::List(_) => &mut b_lists,
+                RiffType::Chunk(_) => &mut b_chunks,
+            };
+            let Some(index) = map.get_mut(a_item.id()).and_then(VecDeque::pop_front) else {
+                self.diffs.push(ChunkDiff::Removed {
+                    path: self.item_path(a_item.id()),
+                    item: a_item,
+                });
+                continue;
+            };
+            matched[index] = true;
+            match (a_item, b_items[index]) {
+                (RiffType::List(a), RiffType::List(b)) => {
+                    self.path.push(a.id());
+                    let result = self.diff_list(a, b);
+                    self.path.pop();
+                    result?;
+                }
+                (RiffType::Chunk(a), RiffType::Chunk(b)) => self.diff_chunk(a, b)?,
+                _ => unreachable!("items are matched by kind"),
+            }
+        }
+
+        for (item, _) in b_items
+            .into_iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+        {
+            self.diffs.push(ChunkDiff::Added {
+                path: self.item_path(item.id()),
+                item,
+            });
+        }
+        Ok(())
+    }
+
+    fn diff_chunk(&mut self, a: Riff<Chunk>, b: Riff<Chunk>) -> RiffResult<()> {
+        let path = self.item_path(a.id());
+        if a.data_size() != b.data_size() {
+            self.diffs.push(ChunkDiff::SizeChanged { path, a, b });
+        } else if self.options.compare_data && !self.b.compare_data(b, &self.a.read_data_vec(a)?)? {
+            self.diffs.push(ChunkDiff::DataChanged { path, a, b });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    Chunk, ChunkReader, List, ListIter, Riff, RiffParser, RiffResult, RiffType, fourcc::Fourcc,
+};
+use ::digest::{Digest, Output};
+use alloc::vec::Vec;
+use binrw::io::{self, Read, Seek};
+
+/// Hashes chunk data as it is read through the wrapped `ChunkReader`
+pub struct DigestChunk<D, R> {
+    reader: ChunkReader<R>,
+    digest: D,
+}
+
+impl<D: Digest, R: Read + Seek> DigestChunk<D, R> {
+    pub fn new(reader: ChunkReader<R>) -> Self {
+        Self {
+            reader,
+            digest: D::new(),
+        }
+    }
+
+    /// Hash of the data read so far
+    pub fn finalize(self) -> Output<D> {
+        self.digest.finalize()
+    }
+
+    /// Read the rest of the chunk data and return the hash of all of it
+    pub fn finalize_remaining(mut self) -> io::Result<Output<D>> {
+        let mut buffer = [0u8; 4096];
+        while self.read(&mut buffer)? != 0 {}
+        Ok(self.finalize())
+    }
+}
+
+impl<D: Digest, R: Read + Seek> Read for DigestChunk<D, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.digest.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Wraps a `ListIter`, hashing the data of every chunk it returns.
+/// Lists are returned but not descended into.
+pub struct DigestList<'a, D: Digest, R> {
+    parser: &'a RiffParser<R>,
+    iter: ListIter<R>,
+    digests: Vec<(Fourcc, Output<D>)>,
+}
+
+impl<'a, D: Digest, R: Read + Seek> DigestList<'a, D, R> {
+    pub fn new(parser: &'a RiffParser<R>, list: Riff<List>) -> Self {
+        Self {
+            parser,
+            iter: parser.chunks(list),
+            digests: Vec::new(),
+        }
+    }
+
+    /// Id and hash of each chunk returned so far
+    pub fn digests(&self) -> &[(Fourcc, Output<D>)] {
+        &self.digests
+    }
+
+    fn digest(&mut self, chunk: Riff<Chunk>) -> RiffResult<()> {
+        let output =
+            DigestChunk::<D, R>::new(chunk.into_reader(self.parser)).finalize_remaining()?;
+        self.digests.push((chunk.id(), output));
+        Ok(())
+    }
+}
+
+impl<D: Digest, R: Read + Seek> Iterator for DigestList<'_, D, R> {
+    type Item = RiffResult<RiffType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        if let Ok(RiffType::Chunk(chunk)) = item
+            && let Err(e) = self.digest(chunk)
+        {
+            return Some(Err(e));
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
@@ -0,0 +1,85 @@
+use alloc::string::String;
+use binrw::io;
+use core::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum RiffError {
+    /// The stream does not start with a `RIFF` header
+    NotRiff,
+    /// A chunk or list is missing, truncated or otherwise invalid
+    MalformedChunk {
+        position: u64,
+        message: String,
+    },
+    Io(io::Error),
+    /// Any other error raised while parsing chunk data
+    Custom(binrw::Error),
+}
+
+pub type RiffResult<T> = Result<T, RiffError>;
+
+impl From<binrw::Error> for RiffError {
+    fn from(err: binrw::Error) -> Self {
+        match err {
+            binrw::Error::Io(err) => RiffError::Io(err),
+            binrw::Error::AssertFail { pos, message } => RiffError::MalformedChunk {
+                position: pos,
+                message,
+            },
+            err => RiffError::Custom(err),
+        }
+    }
+}
+
+impl From<io::Error> for RiffError {
+    fn from(err: io::Error) -> Self {
+        RiffError::Io(err)
+    }
+}
+
+impl Display for RiffError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RiffError::NotRiff => write!(f, "invalid RIFF file"),
+            RiffError::MalformedChunk { position, message } => {
+                write!(f, "malformed chunk at 0x{position:x}: {message}")
+            }
+            RiffError::Io(err) => write!(f, "{err}"),
+            RiffError::Custom(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RiffError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RiffError::Io(err) => Some(err),
+            RiffError::Custom(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// An error with the file it came from and the byte offset being read, see `ContextualParser`
+#[derive(Debug)]
+pub struct ErrorContext<E> {
+    pub inner: E,
+    pub path: Option<String>,
+    pub offset: u64,
+}
+
+impl<E: Display> Display for ErrorContext<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{path}: ")?;
+        }
+        write!(f, "at 0x{:x}: {}", self.offset, self.inner)
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for ErrorContext<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.inner)
+    }
+}
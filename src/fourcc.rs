@@ -1,17 +1,24 @@
+use alloc::vec::Vec;
 use core::{
+    cmp::Ordering,
     fmt::{Debug, Display},
-    ops::Deref,
+    ops::{BitAnd, BitOr, Deref},
 };
 
-use binrw::BinRead;
+use binrw::{BinRead, BinWrite};
 
 pub mod tag {
     use super::Fourcc;
     pub const RIFF: Fourcc = Fourcc::new(*b"RIFF");
+    /// Big endian `RIFF`
+    pub const RIFX: Fourcc = Fourcc::new(*b"RIFX");
     pub const LIST: Fourcc = Fourcc::new(*b"LIST");
 }
 
-#[derive(BinRead, Copy, Clone, Eq, PartialEq)]
+/// Fourccs are bytes, so are always read little endian to keep `new` and `bytes` consistent,
+/// including in big endian `RIFX` files
+#[derive(BinRead, BinWrite, Copy, Clone, Eq, PartialEq, Hash)]
+#[brw(little)]
 pub struct Fourcc(u32);
 
 impl Fourcc {
@@ -26,6 +33,44 @@ impl Fourcc {
     pub const fn bytes(&self) -> [u8; 4] {
         self.0.to_le_bytes()
     }
+
+    /// Whether this is the null `\0\0\0\0` fourcc, which is never valid in a well formed RIFF file
+    pub const fn is_null(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether the first two bytes are `prefix`, e.g. `01` for the `01wb` chunks of an AVI stream
+    pub const fn matches_prefix(&self, prefix: [u8; 2]) -> bool {
+        let bytes = self.bytes();
+        bytes[0] == prefix[0] && bytes[1] == prefix[1]
+    }
+
+    /// Stream index from a two digit decimal prefix like the `01` of `01wb`,
+    /// `None` if the first two bytes aren't digits
+    pub const fn stream_index(&self) -> Option<u32> {
+        match self.bytes() {
+            [tens @ b'0'..=b'9', ones @ b'0'..=b'9', _, _] => {
+                Some(((tens - b'0') * 10 + (ones - b'0')) as u32)
+            }
+            _ => None,
+        }
+    }
+
+    /// The stream data type in the last two bytes, like the `wb` of `01wb`,
+    /// `None` if the fourcc isn't prefixed by a stream index
+    pub const fn stream_type(&self) -> Option<[u8; 2]> {
+        match self.bytes() {
+            [b'0'..=b'9', b'0'..=b'9', a, b] => Some([a, b]),
+            _ => None,
+        }
+    }
+}
+
+/// The null `\0\0\0\0` fourcc, used as an "unset" value
+impl Default for Fourcc {
+    fn default() -> Self {
+        Self::new(*b"\0\0\0\0")
+    }
 }
 
 impl Debug for Fourcc {
@@ -50,6 +95,20 @@ impl Display for Fourcc {
     }
 }
 
+/// Fourccs are ordered lexicographically by their bytes as they appear in the file,
+/// not by the numeric value of the little endian `u32`, so `AVI ` sorts before `WAVE`.
+impl Ord for Fourcc {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bytes().cmp(&other.bytes())
+    }
+}
+
+impl PartialOrd for Fourcc {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Deref for Fourcc {
     type Target = u32;
 
@@ -69,3 +128,231 @@ impl From<u32> for Fourcc {
         Self::from_u32(value)
     }
 }
+
+impl From<Fourcc> for [u8; 4] {
+    fn from(fourcc: Fourcc) -> Self {
+        fourcc.bytes()
+    }
+}
+
+impl From<Fourcc> for u32 {
+    fn from(fourcc: Fourcc) -> Self {
+        fourcc.0
+    }
+}
+
+/// Masks bytes, e.g. `id & Fourcc::new(*b"\0\0\xff\xff")` keeps the `wb` of `01wb`
+impl BitAnd for Fourcc {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// Combines masked bytes, e.g. a two digit stream prefix `01\0\0` with a type `\0\0wb`
+impl BitOr for Fourcc {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Fourcc {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes = self.bytes();
+        if let Ok(s) = str::from_utf8(&bytes) {
+            serializer.serialize_str(s)
+        } else {
+            serializer.serialize_u32(self.0)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Fourcc {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, Unexpected, Visitor};
+
+        struct FourccVisitor;
+
+        impl Visitor<'_> for FourccVisitor {
+            type Value = Fourcc;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str("a four character string or u32")
+            }
+
+            fn visit_str<E: Error>(self, value: &str) -> Result<Fourcc, E> {
+                <[u8; 4]>::try_from(value.as_bytes())
+                    .map(Fourcc::new)
+                    .map_err(|_| E::invalid_length(value.len(), &self))
+            }
+
+            fn visit_u64<E: Error>(self, value: u64) -> Result<Fourcc, E> {
+                u32::try_from(value)
+                    .map(Fourcc::from_u32)
+                    .map_err(|_| E::invalid_value(Unexpected::Unsigned(value), &self))
+            }
+        }
+
+        deserializer.deserialize_any(FourccVisitor)
+    }
+}
+
+/// Map keyed by `Fourcc`, kept sorted for binary search lookups.
+/// Suited to the small sets of well known chunk ids in RIFF files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FourccMap<V> {
+    entries: Vec<(Fourcc, V)>,
+}
+
+impl<V> FourccMap<V> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    fn search(&self, key: Fourcc) -> Result<usize, usize> {
+        self.entries.binary_search_by(|(k, _)| k.cmp(&key))
+    }
+
+    pub fn get(&self, key: Fourcc) -> Option<&V> {
+        let index = self.search(key).ok()?;
+        Some(&self.entries[index].1)
+    }
+
+    pub fn get_mut(&mut self, key: Fourcc) -> Option<&mut V> {
+        let index = self.search(key).ok()?;
+        Some(&mut self.entries[index].1)
+    }
+
+    /// Insert `value`, returning the value previously stored for `key`
+    pub fn insert(&mut self, key: Fourcc, value: V) -> Option<V> {
+        match self.search(key) {
+            Ok(index) => Some(core::mem::replace(&mut self.entries[index].1, value)),
+            Err(index) => {
+                self.entries.insert(index, (key, value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: Fourcc) -> Option<V> {
+        let index = self.search(key).ok()?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn contains_key(&self, key: Fourcc) -> bool {
+        self.search(key).is_ok()
+    }
+
+    /// Entries ordered by key
+    pub fn iter(&self) -> impl Iterator<Item = (Fourcc, &V)> {
+        self.entries.iter().map(|(key, value)| (*key, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<V> Default for FourccMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> FromIterator<(Fourcc, V)> for FourccMap<V> {
+    fn from_iter<I: IntoIterator<Item = (Fourcc, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+/// Map of at most `N` entries stored inline, searched linearly
+#[derive(Debug, Clone)]
+pub struct SmallFourccMap<V, const N: usize> {
+    entries: [Option<(Fourcc, V)>; N],
+}
+
+impl<V, const N: usize> SmallFourccMap<V, N> {
+    pub fn new() -> Self {
+        Self {
+            entries: core::array::from_fn(|_| None),
+        }
+    }
+
+    fn position(&self, key: Fourcc) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| matches!(entry, Some((k, _)) if *k == key))
+    }
+
+    pub fn get(&self, key: Fourcc) -> Option<&V> {
+        let (_, value) = self.entries[self.position(key)?].as_ref()?;
+        Some(value)
+    }
+
+    pub fn get_mut(&mut self, key: Fourcc) -> Option<&mut V> {
+        let index = self.position(key)?;
+        let (_, value) = self.entries[index].as_mut()?;
+        Some(value)
+    }
+
+    /// Insert `value`, returning the value previously stored for `key`.
+    /// Fails returning the entry when the map already holds `N` other keys.
+    pub fn insert(&mut self, key: Fourcc, value: V) -> Result<Option<V>, (Fourcc, V)> {
+        if let Some(index) = self.position(key) {
+            let previous = self.entries[index].replace((key, value));
+            return Ok(previous.map(|(_, value)| value));
+        }
+        match self.entries.iter_mut().find(|entry| entry.is_none()) {
+            Some(entry) => {
+                *entry = Some((key, value));
+                Ok(None)
+            }
+            None => Err((key, value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: Fourcc) -> Option<V> {
+        let index = self.position(key)?;
+        self.entries[index].take().map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, key: Fourcc) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Entries in insertion order, except that removals leave gaps that later inserts fill
+    pub fn iter(&self) -> impl Iterator<Item = (Fourcc, &V)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.as_ref().map(|(key, value)| (*key, value)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<V, const N: usize> Default for SmallFourccMap<V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{List, Riff, RiffParser, RiffResult, RiffType, fourcc::Fourcc};
+use alloc::{string::String, vec::Vec};
+use binrw::io::{Read, Seek};
+
+pub mod tag {
+    use super::Fourcc;
+    /// Digitization date
+    pub const IDIT: Fourcc = Fourcc::new(*b"IDIT");
+    /// Creation date
+    pub const ICRD: Fourcc = Fourcc::new(*b"ICRD");
+    pub const INAM: Fourcc = Fourcc::new(*b"INAM");
+    pub const IART: Fourcc = Fourcc::new(*b"IART");
+    pub const ICMT: Fourcc = Fourcc::new(*b"ICMT");
+    pub const ICOP: Fourcc = Fourcc::new(*b"ICOP");
+    /// Software used to create the file
+    pub const ISFT: Fourcc = Fourcc::new(*b"ISFT");
+}
+
+/// The text chunks of a `LIST INFO`, in file order
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InfoList {
+    pub entries: Vec<(Fourcc, String)>,
+}
+
+impl InfoList {
+    pub fn read<R: Read + Seek>(parser: &RiffParser<R>, list: Riff<List>) -> RiffResult<Self> {
+        let mut entries = Vec::new();
+        for result in parser.chunks(list) {
+            let RiffType::Chunk(chunk) = result? else {
+                continue;
+            };
+            let data = parser.read_data_vec(chunk)?;
+            let text = data.split(|&b| b == 0).next().unwrap_or_default();
+            entries.push((chunk.id(), String::from_utf8_lossy(text).into_owned()));
+        }
+        Ok(Self { entries })
+    }
+
+    /// The text of the first `id` chunk
+    pub fn get(&self, id: Fourcc) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry_id, _)| *entry_id == id)
+            .map(|(_, text)| text.as_str())
+    }
+
+    /// The `IDIT` text without its trailing line break, e.g. `Wed Jan 02 02:03:55 1990`
+    pub fn digitization_date_raw(&self) -> Option<&str> {
+        self.get(tag::IDIT).map(str::trim)
+    }
+
+    /// Parse `IDIT` in the `asctime` format from the AVI spec,
+    /// or the EXIF and ISO 8601 style dates some cameras write instead.
+    /// `IDIT` is free text and the `asctime` weekday is often inconsistent with the date,
+    /// so it's ignored.
+    #[cfg(feature = "chrono")]
+    pub fn digitization_date(&self) -> Option<chrono::NaiveDateTime> {
+        const FORMATS: &[&str] = &[
+            "%Y:%m:%d %H:%M:%S",
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%dT%H:%M:%S",
+        ];
+        let raw = self.digitization_date_raw()?;
+        let parse = |text: &str, format| chrono::NaiveDateTime::parse_from_str(text, format).ok();
+        FORMATS
+            .iter()
+            .find_map(|format| parse(raw, format))
+            .or_else(|| {
+                let (_weekday, rest) = raw.split_once(' ')?;
+                parse(rest.trim_start(), "%b %d %H:%M:%S %Y")
+            })
+    }
+}
@@ -1,66 +1,421 @@
-use binrw::io;
-use embedded_io::{
-    Error as EError, ErrorKind as EErrorKind, Read as ERead, Seek as ESeek, SeekFrom as ESeekFrom,
-};
+use alloc::{collections::BTreeMap, vec::Vec};
+use binrw::io::{self, Read, Seek, SeekFrom};
 
+#[cfg(feature = "embedded-io")]
+mod embedded;
+#[cfg(feature = "embedded-io")]
+pub use embedded::EmbeddedAdapter;
+
+/// Caps reads from the wrapped reader at `limit` bytes. Unlike `Read::take`, reading once
+/// the limit is exhausted fails with `UnexpectedEof` so overruns aren't silently truncated.
+#[derive(Debug)]
+pub struct LimitedReader<R> {
+    reader: R,
+    remaining: u64,
+}
+
+impl<R: Read> LimitedReader<R> {
+    pub fn new(reader: R, limit: u64) -> Self {
+        Self {
+            reader,
+            remaining: limit,
+        }
+    }
+
+    /// Number of bytes that may still be read
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        let len = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let read = self.reader.read(&mut buf[..len])?;
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Counts the bytes read through it, e.g. to check chunk sizes while using a
+/// `StreamingRiffParser` on a reader that can't report its position
+#[derive(Debug)]
+pub struct CountingReader<R> {
+    reader: R,
+    bytes_read: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            bytes_read: 0,
+        }
+    }
+
+    /// Total bytes read so far, seeking doesn't change it
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.bytes_read += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Read + Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+/// Reads a file held in separate buffers, e.g. sectors cached from flash, as one seekable
+/// stream without copying it together. Segments are `(start_offset, data)` pairs sorted by
+/// offset and not overlapping. Reading a gap between segments fails with `UnexpectedEof`,
+/// reading past the end of the last segment returns no bytes.
+#[derive(Debug, Clone)]
+pub struct MultiReader<'a> {
+    segments: &'a [(u64, &'a [u8])],
+    position: u64,
+}
+
+impl<'a> MultiReader<'a> {
+    pub fn new(segments: &'a [(u64, &'a [u8])]) -> Self {
+        Self {
+            segments,
+            position: 0,
+        }
+    }
+
+    /// End offset of the last segment
+    pub fn len(&self) -> u64 {
+        self.segments
+            .last()
+            .map_or(0, |(start, data)| start + data.len() as u64)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Read for MultiReader<'_> {
+    /// Reads at most to the end of the segment holding the position,
+    /// `read_exact` continues into the next segment
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.position >= self.len() {
+            return Ok(0);
+        }
+        // The last segment starting at or before the position
+        let index = self
+            .segments
+            .partition_point(|&(start, _)| start <= self.position);
+        let (start, data) = match index.checked_sub(1) {
+            Some(index) => self.segments[index],
+            None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+        };
+        let Some(available) = data.get((self.position - start) as usize..) else {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        };
+        if available.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        let len = buf.len().min(available.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for MultiReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(position) => (position, 0),
+            SeekFrom::End(offset) => (self.len(), offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        self.position = base
+            .checked_add_signed(offset)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        Ok(self.position)
+    }
+}
+
+/// Presents the part of a larger stream starting at `base_offset` as if it started at byte 0,
+/// e.g. to parse a RIFF file embedded in another container without copying it out. Seeking
+/// before `base_offset` fails with `InvalidInput`.
 #[derive(Debug)]
-pub struct EmbeddedAdapter<T>(pub T);
-
-impl<T> From<T> for EmbeddedAdapter<T> {
-    fn from(inner: T) -> Self {
-        Self(inner)
-    }
-}
-
-fn map_kind(kind: EErrorKind) -> io::ErrorKind {
-    match kind {
-        EErrorKind::NotFound => io::ErrorKind::NotFound,
-        EErrorKind::PermissionDenied => io::ErrorKind::PermissionDenied,
-        EErrorKind::ConnectionRefused => io::ErrorKind::ConnectionRefused,
-        EErrorKind::ConnectionReset => io::ErrorKind::ConnectionReset,
-        EErrorKind::ConnectionAborted => io::ErrorKind::ConnectionAborted,
-        EErrorKind::NotConnected => io::ErrorKind::NotConnected,
-        EErrorKind::AddrInUse => io::ErrorKind::AddrInUse,
-        EErrorKind::AddrNotAvailable => io::ErrorKind::AddrNotAvailable,
-        EErrorKind::BrokenPipe => io::ErrorKind::BrokenPipe,
-        EErrorKind::AlreadyExists => io::ErrorKind::AlreadyExists,
-        EErrorKind::InvalidInput => io::ErrorKind::InvalidInput,
-        EErrorKind::InvalidData => io::ErrorKind::InvalidData,
-        EErrorKind::TimedOut => io::ErrorKind::TimedOut,
-        EErrorKind::Interrupted => io::ErrorKind::Interrupted,
-        EErrorKind::Unsupported => io::ErrorKind::Other, // no matching kind
-        EErrorKind::OutOfMemory => io::ErrorKind::Other, // no matching kind
-        EErrorKind::WriteZero => io::ErrorKind::WriteZero,
-        EErrorKind::Other => io::ErrorKind::Other,
-        _ => io::ErrorKind::Other, // ErrorKind is non_exhaustive
-    }
-}
-
-fn map_error<E: EError>(e: E) -> io::Error {
-    io::Error::from(map_kind(e.kind()))
-}
-
-impl<T> io::Read for EmbeddedAdapter<T>
-where
-    T: ERead,
-    T::Error: EError,
-{
+pub struct OffsetReader<R> {
+    reader: R,
+    base_offset: u64,
+}
+
+impl<R: Read + Seek> OffsetReader<R> {
+    /// Seeks `reader` to `base_offset`, which becomes position 0
+    pub fn new(mut reader: R, base_offset: u64) -> io::Result<Self> {
+        reader.seek(SeekFrom::Start(base_offset))?;
+        Ok(Self {
+            reader,
+            base_offset,
+        })
+    }
+
+    pub fn base_offset(&self) -> u64 {
+        self.base_offset
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek> Read for OffsetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R: Read + Seek> Seek for OffsetReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let invalid = || io::Error::from(io::ErrorKind::InvalidInput);
+        let target = match pos {
+            SeekFrom::Start(position) => {
+                position.checked_add(self.base_offset).ok_or_else(invalid)?
+            }
+            SeekFrom::Current(offset) => self
+                .reader
+                .stream_position()?
+                .checked_add_signed(offset)
+                .ok_or_else(invalid)?,
+            SeekFrom::End(offset) => {
+                let end = self.reader.seek(SeekFrom::End(0))?;
+                end.checked_add_signed(offset).ok_or_else(invalid)?
+            }
+        };
+        if target < self.base_offset {
+            return Err(invalid());
+        }
+        Ok(self.reader.seek(SeekFrom::Start(target))? - self.base_offset)
+    }
+}
+
+/// Keeps everything read through it in memory so reading the same byte range again, e.g. a
+/// player looping over the `movi` chunks, is served without touching the wrapped reader.
+/// Cached ranges are keyed by start offset and never overlap. The cache isn't bounded,
+/// call `clear_cache` to release it.
+#[derive(Debug)]
+pub struct CachingReader<R> {
+    reader: R,
+    cache: BTreeMap<u64, Vec<u8>>,
+    cache_size: usize,
+    position: u64,
+    /// Whether the wrapped reader is at `position`, it falls behind when reads hit the cache
+    in_sync: bool,
+}
+
+impl<R: Read + Seek> CachingReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let position = reader.stream_position()?;
+        Ok(Self {
+            reader,
+            cache: BTreeMap::new(),
+            cache_size: 0,
+            position,
+            in_sync: true,
+        })
+    }
+
+    /// Total bytes of data held in the cache
+    pub fn cache_size_bytes(&self) -> usize {
+        self.cache_size
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+        self.cache_size = 0;
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek> Read for CachingReader<R> {
+    /// Reads at most to the end of a cached range, or up to the start of the next one
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let position = self.position;
+        if let Some((start, data)) = self.cache.range(..=position).next_back()
+            && let Some(available) = data.get((position - start) as usize..)
+            && !available.is_empty()
+        {
+            let len = buf.len().min(available.len());
+            buf[..len].copy_from_slice(&available[..len]);
+            self.position += len as u64;
+            self.in_sync = false;
+            return Ok(len);
+        }
+        let mut len = buf.len();
+        if let Some((&next, _)) = self.cache.range(position + 1..).next() {
+            len = len.min((next - position) as usize);
+        }
+        if !self.in_sync {
+            self.reader.seek(SeekFrom::Start(position))?;
+            self.in_sync = true;
+        }
+        let read = self.reader.read(&mut buf[..len])?;
+        if read > 0 {
+            self.cache.insert(position, buf[..read].to_vec());
+            self.cache_size += read;
+            self.position += read as u64;
+        }
+        Ok(read)
+    }
+}
+
+impl<R: Read + Seek> Seek for CachingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(position) => position,
+            SeekFrom::Current(offset) => self
+                .position
+                .checked_add_signed(offset)
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?,
+            SeekFrom::End(_) => {
+                self.position = self.reader.seek(pos)?;
+                self.in_sync = true;
+                return Ok(self.position);
+            }
+        };
+        if position != self.position {
+            self.position = position;
+            self.in_sync = false;
+        }
+        Ok(self.position)
+    }
+}
+
+/// Buffers reads from the wrapped reader, for readers where every small read is expensive
+/// such as flash on embedded targets. Unlike `std::io::BufReader`, seeking to a position
+/// inside the buffer keeps it, so reading a header then seeking over a short chunk doesn't
+/// refill it. Reads at least as large as the buffer bypass it.
+#[derive(Debug)]
+pub struct BufferedReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// Bytes of `buffer` read from the wrapped reader
+    filled: usize,
+    /// Bytes of `buffer` already returned
+    consumed: usize,
+    /// Position of `buffer[0]` in the wrapped reader, unknown until the first seek.
+    /// The wrapped reader is always at `start + filled`.
+    start: Option<u64>,
+}
+
+impl<R: Read + Seek> BufferedReader<R> {
+    pub fn new(reader: R, capacity: usize) -> Self {
+        Self {
+            reader,
+            buffer: alloc::vec![0; capacity],
+            filled: 0,
+            consumed: 0,
+            start: None,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// The wrapped reader, positioned after the buffered bytes rather than at
+    /// the position of this reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Position of `buffer[0]`, asking the wrapped reader the first time
+    fn start(&mut self) -> io::Result<u64> {
+        if let Some(start) = self.start {
+            return Ok(start);
+        }
+        let start = self.reader.stream_position()? - self.filled as u64;
+        self.start = Some(start);
+        Ok(start)
+    }
+
+    /// Drop the buffer after the wrapped reader moved to `position`
+    fn reset(&mut self, position: u64) {
+        self.start = Some(position);
+        self.filled = 0;
+        self.consumed = 0;
+    }
+}
+
+impl<R: Read + Seek> Read for BufferedReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0.read(buf).map_err(map_error)
+        if self.consumed == self.filled {
+            let end = self.start.map(|start| start + self.filled as u64);
+            if buf.len() >= self.buffer.len() {
+                let read = self.reader.read(buf)?;
+                self.start = end.map(|end| end + read as u64);
+                self.filled = 0;
+                self.consumed = 0;
+                return Ok(read);
+            }
+            self.filled = self.reader.read(&mut self.buffer)?;
+            self.consumed = 0;
+            self.start = end;
+        }
+        let available = &self.buffer[self.consumed..self.filled];
+        let len = buf.len().min(available.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consumed += len;
+        Ok(len)
     }
 }
 
-impl<T> io::Seek for EmbeddedAdapter<T>
-where
-    T: ESeek,
-    T::Error: EError,
-{
-    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
-        let pos = match pos {
-            io::SeekFrom::Start(n) => ESeekFrom::Start(n),
-            io::SeekFrom::End(n) => ESeekFrom::End(n),
-            io::SeekFrom::Current(n) => ESeekFrom::Current(n),
+impl<R: Read + Seek> Seek for BufferedReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(position) => position,
+            SeekFrom::Current(offset) => (self.start()? + self.consumed as u64)
+                .checked_add_signed(offset)
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?,
+            SeekFrom::End(_) => {
+                let position = self.reader.seek(pos)?;
+                self.reset(position);
+                return Ok(position);
+            }
         };
-        self.0.seek(pos).map_err(map_error)
+        let start = self.start()?;
+        if (start..=start + self.filled as u64).contains(&target) {
+            self.consumed = (target - start) as usize;
+            return Ok(target);
+        }
+        self.reader.seek(SeekFrom::Start(target))?;
+        self.reset(target);
+        Ok(target)
     }
 }
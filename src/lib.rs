@@ -2,16 +2,43 @@
 
 extern crate alloc;
 
+pub mod ani;
+#[cfg(feature = "async")]
+pub mod async_io;
 pub mod avi;
+pub mod builder;
+pub mod codec;
+mod context;
+pub mod diff;
+#[cfg(feature = "digest")]
+pub mod digest;
+mod error;
 pub mod fourcc;
-#[cfg(feature = "embedded-io")]
-mod io;
+pub mod info;
+pub mod io;
 #[cfg(feature = "embedded-io")]
 pub use io::EmbeddedAdapter;
 
+pub mod metrics;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 mod riff;
+pub mod scanner;
+pub mod smf;
+mod streaming;
+pub mod tag;
+pub mod wav;
+pub mod webp;
+#[cfg(feature = "std")]
+pub use binrw::io::BufReader;
 pub use binrw::{
     self, Error,
-    io::{Read, Seek, SeekFrom},
+    io::{Cursor, Read, Seek, SeekFrom},
+};
+pub use context::{ContextResult, ContextualParser};
+pub use error::{ErrorContext, RiffError, RiffResult};
+pub use riff::{
+    Chunk, ChunkFilter, ChunkReader, HeaderType, List, ListIter, PeekableListIter, Riff,
+    RiffParser, RiffType, WalkControl, WalkEvent, validate_riff_signature,
 };
-pub use riff::{Chunk, List, ListIter, Riff, RiffParser, RiffType};
+pub use streaming::StreamingRiffParser;
@@ -0,0 +1,74 @@
+use crate::{List, Riff, RiffParser, RiffResult, RiffType, fourcc::FourccMap};
+use alloc::vec::Vec;
+use binrw::io::{Read, Seek};
+
+/// Summary of the chunks and lists in a RIFF file.
+/// Chunk sizes are data sizes, excluding headers and padding.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkStats {
+    pub chunk_count: usize,
+    /// Lists inside the `RIFF` list, which itself is not counted
+    pub list_count: usize,
+    /// Nesting depth of the most deeply nested item, items of the `RIFF` list are at depth 1
+    pub max_depth: usize,
+    pub largest_chunk_size: Option<u32>,
+    pub smallest_chunk_size: Option<u32>,
+    pub mean_chunk_size: Option<f64>,
+    /// Mean of the two middle sizes when there is an even number of chunks
+    pub median_chunk_size: Option<f64>,
+    /// Number of chunks with each id
+    pub chunk_ids: FourccMap<usize>,
+}
+
+impl ChunkStats {
+    /// Walk every list and chunk of the `RIFF` list, reading only their headers
+    pub fn collect<R: Read + Seek>(parser: &RiffParser<R>) -> RiffResult<Self> {
+        let mut stats = Self::default();
+        let mut sizes = Vec::new();
+        stats.walk(parser, parser.riff()?, 1, &mut sizes)?;
+
+        sizes.sort_unstable();
+        stats.largest_chunk_size = sizes.last().copied();
+        stats.smallest_chunk_size = sizes.first().copied();
+        if !sizes.is_empty() {
+            let total: u64 = sizes.iter().map(|&size| size as u64).sum();
+            stats.mean_chunk_size = Some(total as f64 / sizes.len() as f64);
+            let middle = sizes.len() / 2;
+            stats.median_chunk_size = Some(if sizes.len().is_multiple_of(2) {
+                (sizes[middle - 1] as f64 + sizes[middle] as f64) / 2.0
+            } else {
+                sizes[middle] as f64
+            });
+        }
+        Ok(stats)
+    }
+
+    fn walk<R: Read + Seek>(
+        &mut self,
+        parser: &RiffParser<R>,
+        list: Riff<List>,
+        depth: usize,
+        sizes: &mut Vec<u32>,
+    ) -> RiffResult<()> {
+        for item in parser.chunks(list) {
+            self.max_depth = self.max_depth.max(depth);
+            match item? {
+                RiffType::List(list) => {
+                    self.list_count += 1;
+                    self.walk(parser, list, depth + 1, sizes)?;
+                }
+                RiffType::Chunk(chunk) => {
+                    self.chunk_count += 1;
+                    sizes.push(chunk.data_size());
+                    match self.chunk_ids.get_mut(chunk.id()) {
+                        Some(count) => *count += 1,
+                        None => {
+                            self.chunk_ids.insert(chunk.id(), 1);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
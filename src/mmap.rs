@@ -0,0 +1,48 @@
+use crate::{Cursor, RiffParser, RiffResult};
+use core::ops::Deref;
+use memmap2::Mmap;
+use std::{fs::File, path::Path};
+
+/// A `RiffParser` reading a memory mapped file, so headers and chunk data are read
+/// without a syscall each. Derefs to `RiffParser` for the usual API, use `into_inner`
+/// to pass it to a format parser such as `AviParser::new`. The parser reads a
+/// `Cursor<Mmap>` rather than a `Cursor<&[u8]>` so it owns the mapping, with no
+/// lifetime tying it to a separately held `Mmap`.
+pub struct MmapRiffParser {
+    parser: RiffParser<Cursor<Mmap>>,
+}
+
+impl MmapRiffParser {
+    /// Open and map the file at `path`.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated by this or another process while it is
+    /// mapped. The mapping then changes underneath the parser, which is undefined behavior,
+    /// and truncation can crash the process with `SIGBUS`. Only map files the caller knows
+    /// are not being written.
+    pub unsafe fn from_file<P: AsRef<Path>>(path: P) -> RiffResult<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees the file doesn't change while mapped
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            parser: RiffParser::new(Cursor::new(mmap)),
+        })
+    }
+
+    /// The parser rewound to the start of the file, so `riff()` reads the header again
+    /// however much was read through this one
+    pub fn into_inner(self) -> RiffParser<Cursor<Mmap>> {
+        // Seeking a cursor to the start can't fail
+        let _ = self.parser.rewind();
+        self.parser
+    }
+}
+
+impl Deref for MmapRiffParser {
+    type Target = RiffParser<Cursor<Mmap>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.parser
+    }
+}
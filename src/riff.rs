@@ -1,101 +1,499 @@
 use alloc::rc::Rc;
-use alloc::{boxed::Box, vec, vec::Vec};
+use alloc::{collections::VecDeque, format, string::String, vec, vec::Vec};
 use binrw::io::TakeSeekExt;
 use binrw::{
-    BinRead, BinResult, Error as BinError,
-    io::{Read, Seek, SeekFrom},
-    meta::ReadEndian,
+    BinRead, BinWrite, Endian, Error as BinError,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+};
+use core::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+    iter::Iterator,
+    mem::size_of,
+    ops::Range,
 };
-use core::{cell::RefCell, fmt::Debug, iter::Iterator, mem::size_of};
 
-use crate::fourcc::Fourcc;
+use crate::error::{RiffError, RiffResult};
+use crate::fourcc::{Fourcc, tag};
+use crate::io::BufferedReader;
 
 pub struct RiffParser<R> {
     reader: Rc<RefCell<R>>,
+    /// Big for `RIFX` files, set when the `RIFF` header is read
+    endian: Cell<Endian>,
+    /// Bumped by every `ListIter` header read, shared by the iterators of this reader
+    #[cfg(all(feature = "log", debug_assertions))]
+    generation: Rc<Cell<u64>>,
 }
 
 impl<R: Read + Seek> RiffParser<R> {
     pub fn new(reader: R) -> Self {
         Self {
             reader: Rc::new(RefCell::new(reader)),
+            endian: Cell::new(Endian::Little),
+            #[cfg(all(feature = "log", debug_assertions))]
+            generation: Rc::new(Cell::new(0)),
         }
     }
 
-    pub fn riff(&self) -> BinResult<Riff<List>> {
+    /// Seek the reader back to the start, where `riff` reads the header from
+    #[cfg(feature = "mmap")]
+    pub(crate) fn rewind(&self) -> RiffResult<()> {
+        self.reader.borrow_mut().seek(SeekFrom::Start(0))?;
+        Ok(())
+    }
+
+    /// Read the `RIFF` header, or the big endian `RIFX` header. The byte order it implies is
+    /// used for every header read afterwards and by `read_data_struct`.
+    pub fn riff(&self) -> RiffResult<Riff<List>> {
         let mut reader = self.reader.borrow_mut();
-        let header = match HeaderType::read(&mut *reader) {
-            Ok(HeaderType::Riff(header)) => header,
-            Ok(_) => {
-                return Err(BinError::Custom {
-                    pos: 0,
-                    err: Box::new("invalid RIFF file"),
-                });
-            }
-            Err(e) => return Err(e),
+        let start = reader.stream_position()?;
+        let mut magic = [0u8; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|err| header_io_error(err, RiffError::NotRiff))?;
+        let endian = match Fourcc::new(magic) {
+            tag::RIFF => Endian::Little,
+            tag::RIFX => Endian::Big,
+            _ => return Err(RiffError::NotRiff),
+        };
+        reader.seek(SeekFrom::Start(start))?;
+        let (HeaderType::Riff(header) | HeaderType::Rifx(header)) =
+            HeaderType::read_options(&mut *reader, endian, ())
+                .map_err(|err| header_error(err, RiffError::NotRiff))?
+        else {
+            return Err(RiffError::NotRiff);
         };
-        let data_start = reader.stream_position().map_err(BinError::Io)?;
+        self.endian.set(endian);
+        let data_start = reader.stream_position()?;
         Ok(Riff::new(header, data_start))
     }
 
+    /// Read the first header like `riff`, also accepting the `LIST` or plain chunk some
+    /// embedded uses of the format start with. `RIFF`, `RIFX` and `LIST` are returned as
+    /// lists, use `assert_is_riff` to require `RIFF` or `RIFX` as `riff` does.
+    pub fn top_level(&self) -> RiffResult<RiffType> {
+        let mut reader = self.reader.borrow_mut();
+        let start = reader.stream_position()?;
+        let mut magic = [0u8; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|err| header_io_error(err, RiffError::NotRiff))?;
+        let endian = match Fourcc::new(magic) {
+            tag::RIFX => Endian::Big,
+            _ => Endian::Little,
+        };
+        reader.seek(SeekFrom::Start(start))?;
+        let header = HeaderType::read_options(&mut *reader, endian, ())
+            .map_err(|err| header_error(err, RiffError::NotRiff))?;
+        self.endian.set(endian);
+        let data_start = reader.stream_position()?;
+        Ok(match header {
+            HeaderType::Riff(list) | HeaderType::Rifx(list) | HeaderType::List(list) => {
+                RiffType::List(Riff::new(list, data_start))
+            }
+            HeaderType::Chunk(chunk) => RiffType::Chunk(Riff::new(chunk, data_start)),
+        })
+    }
+
+    /// The list returned by `top_level` if its header is `RIFF` or `RIFX`, otherwise `NotRiff`
+    pub fn assert_is_riff(&self, item: RiffType) -> RiffResult<Riff<List>> {
+        let RiffType::List(list) = item else {
+            return Err(RiffError::NotRiff);
+        };
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(list.header_position()))?;
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        reader.seek(SeekFrom::Start(list.position()))?;
+        match Fourcc::new(magic) {
+            tag::RIFF | tag::RIFX => Ok(list),
+            _ => Err(RiffError::NotRiff),
+        }
+    }
+
+    /// Byte order of the file, little endian unless `riff` read a `RIFX` header
+    pub fn endian(&self) -> Endian {
+        self.endian.get()
+    }
+
+    /// Read a `RIFF` header at `position`, e.g. an OpenDML `RIFF AVIX` following the first `RIFF`
+    pub fn riff_at(&self, position: u64) -> RiffResult<Riff<List>> {
+        self.reader.borrow_mut().seek(SeekFrom::Start(position))?;
+        self.riff()
+    }
+
+    pub(crate) fn stream_len(&self) -> RiffResult<u64> {
+        Ok(self.reader.borrow_mut().seek(SeekFrom::End(0))?)
+    }
+
     pub fn chunks(&self, chunk: Riff<List>) -> ListIter<R> {
-        ListIter::new(chunk, Rc::clone(&self.reader))
+        let iter = ListIter::new(chunk, Rc::clone(&self.reader), self.endian());
+        #[cfg(all(feature = "log", debug_assertions))]
+        let iter = iter.with_generation(Rc::clone(&self.generation));
+        iter
+    }
+
+    /// Count the chunks and lists directly inside `list`, reading only their headers
+    pub fn chunk_count(&self, list: Riff<List>) -> RiffResult<u32> {
+        self.chunks(list)
+            .try_fold(0, |count, result| result.map(|_| count + 1))
+    }
+
+    /// The first chunk directly inside `list` with this id, stopping at the first match
+    pub fn find_chunk(&self, list: Riff<List>, id: Fourcc) -> RiffResult<Option<Riff<Chunk>>> {
+        for item in self.chunks(list) {
+            if let RiffType::Chunk(chunk) = item?
+                && chunk.id() == id
+            {
+                return Ok(Some(chunk));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The first list directly inside `list` with this list type, stopping at the first match
+    pub fn find_list(&self, list: Riff<List>, id: Fourcc) -> RiffResult<Option<Riff<List>>> {
+        for item in self.chunks(list) {
+            if let RiffType::List(found) = item?
+                && found.id() == id
+            {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
     }
 
-    pub fn read_data_struct<S>(&self, chunk: Riff<Chunk>) -> BinResult<S>
+    /// Visit every list and chunk of the `RIFF` list read by `riff`, depth first in file order,
+    /// without writing the recursion over `chunks`. `callback` may read chunk data.
+    pub fn depth_first_walk<F>(&self, mut callback: F) -> RiffResult<()>
     where
-        S: BinRead + ReadEndian + Sized,
+        F: FnMut(WalkEvent) -> WalkControl,
+    {
+        let riff = self.riff()?;
+        self.walk_list(riff, &mut callback)?;
+        Ok(())
+    }
+
+    /// Returns false once the walk is aborted
+    fn walk_list<F>(&self, list: Riff<List>, callback: &mut F) -> RiffResult<bool>
+    where
+        F: FnMut(WalkEvent) -> WalkControl,
+    {
+        match callback(WalkEvent::Enter(list)) {
+            WalkControl::Abort => return Ok(false),
+            WalkControl::Skip => {}
+            WalkControl::Continue => {
+                for item in self.chunks(list) {
+                    let proceed = match item? {
+                        RiffType::List(list) => self.walk_list(list, callback)?,
+                        RiffType::Chunk(chunk) => {
+                            callback(WalkEvent::Chunk(chunk)) != WalkControl::Abort
+                        }
+                    };
+                    if !proceed {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        Ok(callback(WalkEvent::Exit(list)) != WalkControl::Abort)
+    }
+
+    pub fn chunks_filtered(
+        &self,
+        chunk: Riff<List>,
+        filter: ChunkFilter,
+    ) -> impl Iterator<Item = RiffResult<RiffType>> + use<R> {
+        filter.build(self.chunks(chunk))
+    }
+
+    pub(crate) fn read_header_at(&self, position: u64) -> RiffResult<RiffType> {
+        read_header(&mut *self.reader.borrow_mut(), position, self.endian())
+    }
+
+    pub fn read_data_struct<S>(&self, chunk: Riff<Chunk>) -> RiffResult<S>
+    where
+        S: BinRead + Sized,
         for<'a> <S as BinRead>::Args<'a>: Default,
     {
+        self.read_data_struct_at(chunk, 0)
+    }
+
+    /// Parse `S` starting `offset` bytes into the data of `chunk`, e.g. after a variable
+    /// length header. Reading past the end of the chunk data fails.
+    /// Types without a fixed byte order are read in the byte order of the file.
+    pub fn read_data_struct_at<S>(&self, chunk: Riff<Chunk>, offset: u32) -> RiffResult<S>
+    where
+        S: BinRead + Sized,
+        for<'a> <S as BinRead>::Args<'a>: Default,
+    {
+        self.read_data_struct_with_args(chunk, offset, Default::default())
+    }
+
+    /// Like `read_data_struct_at`, for types that need explicit `BinRead` arguments,
+    /// e.g. a codec header whose size depends on another field
+    pub fn read_data_struct_with_args<'a, S>(
+        &self,
+        chunk: Riff<Chunk>,
+        offset: u32,
+        args: S::Args<'a>,
+    ) -> RiffResult<S>
+    where
+        S: BinRead + Sized,
+    {
+        if offset > chunk.data_size() {
+            return Err(RiffError::MalformedChunk {
+                position: chunk.data_start,
+                message: "offset past end of chunk".into(),
+            });
+        }
         let mut reader = self.reader.borrow_mut();
-        reader
-            .seek(SeekFrom::Start(chunk.data_start))
-            .map_err(BinError::Io)?;
-        let mut limited_reader = reader.by_ref().take_seek(chunk.data_size() as u64);
-        S::read(&mut limited_reader)
+        reader.seek(SeekFrom::Start(chunk.data_start + offset as u64))?;
+        let mut limited_reader = reader
+            .by_ref()
+            .take_seek(chunk.data_size().saturating_sub(offset) as u64);
+        Ok(S::read_options(&mut limited_reader, self.endian(), args)?)
     }
 
-    pub fn read_data_vec<H: Header>(&self, chunk: Riff<H>) -> BinResult<Vec<u8>> {
+    /// Read all the data of `chunk`, an empty chunk reads as an empty `Vec`
+    pub fn read_data_vec<H: Header>(&self, chunk: Riff<H>) -> RiffResult<Vec<u8>> {
         let data_size = chunk.data_size();
         let mut buffer = vec![0u8; data_size as usize];
         self.read_data(chunk, &mut buffer)?;
         Ok(buffer)
     }
 
-    pub fn read_data<H: Header>(&self, chunk: Riff<H>, buffer: &mut [u8]) -> BinResult<()> {
-        let data_size = chunk.data_size();
+    /// Read the data of a text chunk like `INAM` as UTF-8, without trailing null bytes
+    pub fn read_data_string(&self, chunk: Riff<Chunk>) -> RiffResult<String> {
+        let mut data = self.read_data_vec(chunk)?;
+        trim_nulls(&mut data);
+        String::from_utf8(data).map_err(|_| RiffError::MalformedChunk {
+            position: chunk.data_start,
+            message: format!("{} is not valid UTF-8", chunk.id()),
+        })
+    }
+
+    /// Like `read_data_string`, replacing invalid UTF-8 with `U+FFFD`
+    pub fn read_data_string_lossy(&self, chunk: Riff<Chunk>) -> RiffResult<String> {
+        let mut data = self.read_data_vec(chunk)?;
+        trim_nulls(&mut data);
+        Ok(String::from_utf8_lossy(&data).into_owned())
+    }
+
+    /// Seek the reader past the data (and padding) of `chunk` without reading it
+    pub fn skip<H: Header>(&self, chunk: Riff<H>) -> RiffResult<()> {
         let mut reader = self.reader.borrow_mut();
-        if buffer.len() > data_size as usize {
-            return Err(BinError::AssertFail {
-                pos: chunk.data_start,
+        reader.seek(SeekFrom::Start(chunk.end_position()))?;
+        Ok(())
+    }
+
+    pub fn read_data<H: Header>(&self, chunk: Riff<H>, buffer: &mut [u8]) -> RiffResult<()> {
+        self.read_data_partial(chunk, 0, buffer)
+    }
+
+    /// Compare the data of `chunk` with `expected` without reading it into a `Vec`.
+    /// Every byte is compared even after a mismatch, only a size difference returns early.
+    pub fn compare_data<H: Header>(&self, chunk: Riff<H>, expected: &[u8]) -> RiffResult<bool> {
+        if chunk.data_size() as usize != expected.len() {
+            return Ok(false);
+        }
+        let mut buffer = [0u8; 256];
+        let mut diff = 0;
+        let mut offset = 0;
+        for block in expected.chunks(buffer.len()) {
+            let data = &mut buffer[..block.len()];
+            self.read_data_partial(chunk, offset, data)?;
+            diff |= data.iter().zip(block).fold(0, |acc, (a, b)| acc | (a ^ b));
+            offset += block.len() as u32;
+        }
+        Ok(diff == 0)
+    }
+
+    /// Read `buffer.len()` bytes starting `byte_offset` bytes into the data of `chunk`
+    pub fn read_data_partial<H: Header>(
+        &self,
+        chunk: Riff<H>,
+        byte_offset: u32,
+        buffer: &mut [u8],
+    ) -> RiffResult<()> {
+        let data_size = chunk.data_size() as u64;
+        let mut reader = self.reader.borrow_mut();
+        if byte_offset as u64 + buffer.len() as u64 > data_size {
+            return Err(RiffError::MalformedChunk {
+                position: chunk.data_start,
                 message: "buffer too large".into(),
             });
         }
-        reader
-            .seek(SeekFrom::Start(chunk.data_start))
-            .map_err(BinError::Io)?;
-        reader.read_exact(buffer).map_err(BinError::Io)?;
+        reader.seek(SeekFrom::Start(chunk.data_start + byte_offset as u64))?;
+        reader.read_exact(buffer)?;
         Ok(())
     }
 }
 
+impl<'a> RiffParser<Cursor<&'a [u8]>> {
+    /// Parse RIFF data from a byte slice
+    ///
+    /// ```
+    /// use riffparse::{RiffParser, fourcc::Fourcc};
+    ///
+    /// let data: &[u8] = b"RIFF\x0c\0\0\0WAVEdata\0\0\0\0";
+    /// let parser = RiffParser::from_bytes(data);
+    /// let riff = parser.riff().unwrap();
+    /// assert_eq!(riff.id(), Fourcc::new(*b"WAVE"));
+    /// assert_eq!(parser.chunks(riff).count(), 1);
+    /// ```
+    pub fn from_bytes(data: &'a [u8]) -> Self {
+        Self::new(Cursor::new(data))
+    }
+}
+
+impl RiffParser<Cursor<Vec<u8>>> {
+    /// Parse RIFF data from an owned buffer
+    ///
+    /// ```
+    /// use riffparse::{RiffParser, fourcc::Fourcc};
+    ///
+    /// let data = b"RIFF\x0c\0\0\0WAVEdata\0\0\0\0".to_vec();
+    /// let parser = RiffParser::from_vec(data);
+    /// assert_eq!(parser.riff().unwrap().id(), Fourcc::new(*b"WAVE"));
+    /// ```
+    pub fn from_vec(data: Vec<u8>) -> Self {
+        Self::new(Cursor::new(data))
+    }
+}
+
+impl<R: Read + Seek> RiffParser<BufferedReader<R>> {
+    /// Wrap `reader` in a `BufferedReader` with a `buf_size` byte buffer,
+    /// useful when every small header read is expensive (e.g. flash on embedded targets)
+    pub fn new_buffered(reader: R, buf_size: usize) -> Self {
+        Self::new(BufferedReader::new(reader, buf_size))
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum RiffType {
     List(Riff<List>),
     Chunk(Riff<Chunk>),
 }
 
+impl RiffType {
+    pub fn id(&self) -> Fourcc {
+        match self {
+            RiffType::List(list) => list.id(),
+            RiffType::Chunk(chunk) => chunk.id(),
+        }
+    }
+}
+
+/// Reported by `RiffParser::depth_first_walk`
+#[derive(Debug, Copy, Clone)]
+pub enum WalkEvent {
+    /// Before the items of the list, including the `RIFF` list
+    Enter(Riff<List>),
+    /// After the items of the list
+    Exit(Riff<List>),
+    Chunk(Riff<Chunk>),
+}
+
+/// Returned from the `RiffParser::depth_first_walk` callback
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WalkControl {
+    Continue,
+    /// When returned for `Enter`, skip the items of the list. Its `Exit` is still reported.
+    Skip,
+    /// Stop the walk, which then returns `Ok`
+    Abort,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ChunkKind {
+    Lists,
+    Chunks,
+}
+
+/// Declarative selection of the items returned by a `ListIter`.
+/// Errors are always passed through.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkFilter {
+    include: Vec<Fourcc>,
+    exclude: Vec<Fourcc>,
+    kind: Option<ChunkKind>,
+}
+
+impl ChunkFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only return items with this id, may be called multiple times to include several ids
+    pub fn include_id(mut self, id: Fourcc) -> Self {
+        self.include.push(id);
+        self
+    }
+
+    pub fn exclude_id(mut self, id: Fourcc) -> Self {
+        self.exclude.push(id);
+        self
+    }
+
+    pub fn lists_only(mut self) -> Self {
+        self.kind = Some(ChunkKind::Lists);
+        self
+    }
+
+    pub fn chunks_only(mut self) -> Self {
+        self.kind = Some(ChunkKind::Chunks);
+        self
+    }
+
+    pub fn matches(&self, item: &RiffType) -> bool {
+        let kind_matches = matches!(
+            (self.kind, item),
+            (None, _)
+                | (Some(ChunkKind::Lists), RiffType::List(_))
+                | (Some(ChunkKind::Chunks), RiffType::Chunk(_))
+        );
+        let id = item.id();
+        kind_matches
+            && (self.include.is_empty() || self.include.contains(&id))
+            && !self.exclude.contains(&id)
+    }
+
+    pub fn build<R: Read + Seek>(
+        self,
+        iter: ListIter<R>,
+    ) -> impl Iterator<Item = RiffResult<RiffType>> {
+        iter.filter(move |result| match result {
+            Ok(item) => self.matches(item),
+            Err(_) => true,
+        })
+    }
+}
+
 pub trait Header: Copy + Clone + Debug {
+    /// Bytes of the header preceding the data
+    const SIZE: u64;
+
     fn id(&self) -> Fourcc;
     fn data_size(&self) -> u32;
 }
 
-#[derive(BinRead, Debug, Copy, Clone)]
+#[derive(BinRead, BinWrite, Debug, Copy, Clone)]
 pub struct Chunk {
     chunk_id: Fourcc,
     size: u32,
 }
 
+impl Chunk {
+    pub(crate) fn new(chunk_id: Fourcc, size: u32) -> Self {
+        Self { chunk_id, size }
+    }
+}
+
 impl Header for Chunk {
+    const SIZE: u64 = 8;
+
     fn id(&self) -> Fourcc {
         self.chunk_id
     }
@@ -105,13 +503,21 @@ impl Header for Chunk {
     }
 }
 
-#[derive(BinRead, Debug, Copy, Clone)]
+#[derive(BinRead, BinWrite, Debug, Copy, Clone)]
 pub struct List {
     size: u32,
     list_id: Fourcc,
 }
 
+impl List {
+    pub(crate) fn new(size: u32, list_id: Fourcc) -> Self {
+        Self { size, list_id }
+    }
+}
+
 impl Header for List {
+    const SIZE: u64 = 12;
+
     fn id(&self) -> Fourcc {
         self.list_id
     }
@@ -123,16 +529,60 @@ impl Header for List {
     }
 }
 
-#[derive(BinRead, Debug, Copy, Clone)]
-#[br(little)]
-enum HeaderType {
-    #[br(magic = b"RIFF")]
+/// A header as it appears in the file. Sizes are the raw header field,
+/// so for `RIFF` and `LIST` they include the 4 byte form type or list id.
+/// Sizes are big endian in `RIFX` files, and little endian otherwise.
+#[derive(BinRead, BinWrite, Debug, Copy, Clone)]
+pub enum HeaderType {
+    #[brw(magic = b"RIFF")]
     Riff(List),
-    #[br(magic = b"LIST")]
+    #[brw(magic = b"RIFX")]
+    Rifx(List),
+    #[brw(magic = b"LIST")]
     List(List),
     Chunk(Chunk),
 }
 
+impl HeaderType {
+    pub fn riff(form_type: Fourcc, size: u32) -> Self {
+        HeaderType::Riff(List::new(size, form_type))
+    }
+
+    pub fn list(list_id: Fourcc, size: u32) -> Self {
+        HeaderType::List(List::new(size, list_id))
+    }
+
+    pub fn chunk(chunk_id: Fourcc, size: u32) -> Self {
+        HeaderType::Chunk(Chunk::new(chunk_id, size))
+    }
+
+    pub fn rifx(form_type: Fourcc, size: u32) -> Self {
+        HeaderType::Rifx(List::new(size, form_type))
+    }
+
+    /// Write the header little endian, 12 bytes for `RIFF` and `LIST` or 8 for a chunk
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.write_endian(writer, Endian::Little)
+    }
+
+    /// Write the header with sizes in `endian` byte order, e.g. big endian inside a `RIFX`
+    pub fn write_endian<W: Write>(&self, writer: &mut W, endian: Endian) -> io::Result<()> {
+        let mut buffer = [0u8; 12];
+        let mut cursor = Cursor::new(&mut buffer[..]);
+        match self.write_options(&mut cursor, endian, ()) {
+            Ok(()) => {}
+            Err(BinError::Io(err)) => return Err(err),
+            // Only IO errors are possible writing these fields
+            Err(err) => unreachable!("{err}"),
+        }
+        let len = cursor.position() as usize;
+        writer.write_all(&buffer[..len])
+    }
+}
+
+/// A list or chunk located in the file. The header type statically distinguishes the two,
+/// only a `Riff<List>` can be iterated with `RiffParser::chunks` and only a `Riff<Chunk>`
+/// can be parsed with `RiffParser::read_data_struct`. `RiffType` is used where either may occur.
 #[derive(Debug, Copy, Clone)]
 pub struct Riff<H> {
     header: H,
@@ -140,7 +590,7 @@ pub struct Riff<H> {
 }
 
 impl<H: Header> Riff<H> {
-    fn new(header: H, data_start: u64) -> Self {
+    pub(crate) fn new(header: H, data_start: u64) -> Self {
         Self { header, data_start }
     }
 
@@ -152,10 +602,40 @@ impl<H: Header> Riff<H> {
         }
     }
 
+    pub(crate) fn end_position(&self) -> u64 {
+        self.data_start + self.data_size() as u64 + self.data_pad() as u64
+    }
+
+    /// Where the data starts, after the header (and the type of a list)
     pub fn position(&self) -> u64 {
         self.data_start
     }
 
+    /// Where the header starts, the id of a chunk or `LIST` of a list
+    pub fn header_position(&self) -> u64 {
+        self.data_start - H::SIZE
+    }
+
+    /// Same as `position`
+    pub fn data_position(&self) -> u64 {
+        self.data_start
+    }
+
+    /// Offsets of the data, excluding any pad byte
+    pub fn byte_range(&self) -> Range<u64> {
+        self.data_start..self.data_start + self.data_size() as u64
+    }
+
+    /// Offsets occupied in the file, from the header to the pad byte if any
+    pub fn full_byte_range(&self) -> Range<u64> {
+        self.header_position()..self.end_position()
+    }
+
+    /// Whether `offset` is within the data, e.g. a chunk header inside a list
+    pub fn contains_offset(&self, offset: u64) -> bool {
+        self.byte_range().contains(&offset)
+    }
+
     pub fn data_size(&self) -> u32 {
         self.header.data_size()
     }
@@ -163,70 +643,437 @@ impl<H: Header> Riff<H> {
     pub fn id(&self) -> Fourcc {
         self.header.id()
     }
+
+    /// Bytes occupied in the file including the header and any pad byte,
+    /// unlike `data_size` which is the (possibly odd) size from the header
+    pub fn total_size_on_disk(&self) -> u64 {
+        H::SIZE + self.data_size() as u64 + self.data_pad() as u64
+    }
+
+    /// Check the data size is exactly `expected`, for fixed size chunks like `avih`
+    pub fn verify_size(&self, expected: u32) -> RiffResult<()> {
+        if self.data_size() == expected {
+            Ok(())
+        } else {
+            Err(RiffError::MalformedChunk {
+                position: self.data_start,
+                message: format!(
+                    "{} size {}, expected {}",
+                    self.id(),
+                    self.data_size(),
+                    expected
+                ),
+            })
+        }
+    }
+}
+
+impl Riff<Chunk> {
+    /// Whether the chunk has no data, zero length chunks are valid and used as markers
+    pub fn is_empty(&self) -> bool {
+        self.data_size() == 0
+    }
+
+    pub fn has_data(&self) -> bool {
+        !self.is_empty()
+    }
+
+    /// Stream the data of this chunk from `parser` instead of reading it into memory at once
+    pub fn into_reader<R: Read + Seek>(self, parser: &RiffParser<R>) -> ChunkReader<R> {
+        ChunkReader {
+            reader: parser.reader.clone(),
+            position: self.data_start,
+            end: self.data_start + self.data_size() as u64,
+        }
+    }
+}
+
+impl Riff<List> {
+    /// Whether the list holds no items, only its list id
+    pub fn is_empty(&self) -> bool {
+        self.data_size() == 0
+    }
 }
 
 pub struct ListIter<R> {
     reader: Rc<RefCell<R>>,
     list: Riff<List>,
     next_position: u64,
+    endian: Endian,
+    /// Remaining items, scanned ahead once reverse iteration starts
+    buffered: Option<VecDeque<RiffResult<RiffType>>>,
+    /// Reader generation when this iterator was created and after its last header read
+    #[cfg(all(feature = "log", debug_assertions))]
+    generation: Option<ListIterGeneration>,
+}
+
+#[cfg(all(feature = "log", debug_assertions))]
+struct ListIterGeneration {
+    shared: Rc<Cell<u64>>,
+    created: u64,
+    last_read: Option<u64>,
 }
 
 impl<R: Read + Seek> ListIter<R> {
-    fn new(list: Riff<List>, reader: Rc<RefCell<R>>) -> Self {
+    fn new(list: Riff<List>, reader: Rc<RefCell<R>>, endian: Endian) -> Self {
         Self {
             reader,
+            endian,
             next_position: list.data_start,
             list,
+            buffered: None,
+            #[cfg(all(feature = "log", debug_assertions))]
+            generation: None,
         }
     }
 
+    #[cfg(all(feature = "log", debug_assertions))]
+    fn with_generation(mut self, shared: Rc<Cell<u64>>) -> Self {
+        self.generation = Some(ListIterGeneration {
+            created: shared.get(),
+            shared,
+            last_read: None,
+        });
+        self
+    }
+
+    /// Byte offset of the header of the next item this iterator will return.
+    /// Once `next_back` has been called the remaining items are buffered
+    /// and this is the end of the list.
     pub fn position(&self) -> u64 {
         self.next_position
     }
 
-    fn read_next(&mut self) -> BinResult<RiffType> {
+    /// Wraps this iterator so the next item can be inspected before it's consumed
+    pub fn peekable_chunk(self) -> PeekableListIter<R> {
+        PeekableListIter {
+            iter: self,
+            peeked: None,
+            failed: false,
+        }
+    }
+
+    fn read_next(&mut self) -> RiffResult<RiffType> {
+        #[cfg(all(feature = "log", debug_assertions))]
+        self.check_generation();
         let mut reader = self.reader.borrow_mut();
-        reader
-            .seek(SeekFrom::Start(self.next_position))
-            .map_err(BinError::Io)?;
-        match HeaderType::read(&mut *reader) {
-            Ok(header) => {
-                let data_start = reader.stream_position().map_err(BinError::Io)?;
-                match header {
-                    HeaderType::List(list_header) => {
-                        let list = Riff::new(list_header, data_start);
-                        self.next_position =
-                            data_start + (list.data_size() + list.data_pad()) as u64;
-                        Ok(RiffType::List(list))
-                    }
-                    HeaderType::Chunk(chunk_header) => {
-                        let chunk = Riff::new(chunk_header, data_start);
-                        self.next_position =
-                            data_start + (chunk.data_size() + chunk.data_pad()) as u64;
-                        Ok(RiffType::Chunk(chunk))
-                    }
-                    HeaderType::Riff(_) => Err(BinError::Custom {
-                        pos: data_start,
-                        err: Box::new("malformed RIFF file"),
-                    }),
+        let item = read_header(&mut *reader, self.next_position, self.endian)?;
+        self.next_position = match item {
+            RiffType::List(list) => list.end_position(),
+            RiffType::Chunk(chunk) => chunk.end_position(),
+        };
+        Ok(item)
+    }
+
+    /// Warn when another live iterator of the same reader read a header since this one
+    /// last did. Data reads don't count, and neither do nested iterators dropped before
+    /// this one advances again.
+    #[cfg(all(feature = "log", debug_assertions))]
+    fn check_generation(&mut self) {
+        let Some(generation) = &mut self.generation else {
+            return;
+        };
+        let current = generation.shared.get();
+        if let Some(last_read) = generation.last_read
+            && last_read != current
+        {
+            log::warn!(
+                "interleaved iterators in {} list at 0x{:x}: another iterator advanced the reader",
+                self.list.id(),
+                self.list.position(),
+            );
+        }
+        let next = current.wrapping_add(1);
+        generation.shared.set(next);
+        generation.last_read = Some(next);
+    }
+}
+
+/// Rewind the reader generation if this iterator made the last read, so iterators nested
+/// inside another one don't make the outer one warn
+#[cfg(all(feature = "log", debug_assertions))]
+impl<R> Drop for ListIter<R> {
+    fn drop(&mut self) {
+        if let Some(generation) = &self.generation
+            && generation.last_read == Some(generation.shared.get())
+        {
+            generation.shared.set(generation.created);
+        }
+    }
+}
+
+fn read_header<R: Read + Seek>(
+    reader: &mut R,
+    position: u64,
+    endian: Endian,
+) -> RiffResult<RiffType> {
+    reader.seek(SeekFrom::Start(position))?;
+    match HeaderType::read_options(reader, endian, ()) {
+        Ok(header) => {
+            let data_start = reader.stream_position()?;
+            match header {
+                HeaderType::List(list_header) => {
+                    Ok(RiffType::List(Riff::new(list_header, data_start)))
                 }
+                HeaderType::Chunk(chunk_header) => {
+                    Ok(RiffType::Chunk(Riff::new(chunk_header, data_start)))
+                }
+                HeaderType::Riff(_) | HeaderType::Rifx(_) => Err(RiffError::MalformedChunk {
+                    position: data_start,
+                    message: "nested RIFF header".into(),
+                }),
             }
-            Err(e) => Err(e),
         }
+        Err(err) => Err(header_error(
+            err,
+            RiffError::MalformedChunk {
+                position,
+                message: "truncated chunk header".into(),
+            },
+        )),
     }
 }
 
 impl<R: Read + Seek> Iterator for ListIter<R> {
-    type Item = BinResult<RiffType>;
+    type Item = RiffResult<RiffType>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.next_position
-            >= self.list.data_start + (self.list.data_size() + self.list.data_pad()) as u64
-                - size_of::<Fourcc>() as u64
-        {
+        if let Some(buffered) = &mut self.buffered {
+            buffered.pop_front()
+        } else if self.next_position >= self.list.end_position() - size_of::<Fourcc>() as u64 {
             None
         } else {
             Some(self.read_next())
         }
     }
+
+    /// Every item has at least an 8 byte header, which bounds how many can remain
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if let Some(buffered) = &self.buffered {
+            return (buffered.len(), Some(buffered.len()));
+        }
+        let remaining = self.list.end_position().saturating_sub(self.next_position) / 8;
+        (0, Some(usize::try_from(remaining).unwrap_or(usize::MAX)))
+    }
+}
+
+/// `ListIter` with lookahead, like `core::iter::Peekable` but keeping `position`
+/// Unlike `ListIter`, it's fused after an error: once an error is returned, by `next` or
+/// `peek`, no further items are read.
+pub struct PeekableListIter<R> {
+    iter: ListIter<R>,
+    /// The peeked item and the position of its header
+    peeked: Option<(u64, Option<RiffResult<RiffType>>)>,
+    failed: bool,
+}
+
+impl<R: Read + Seek> PeekableListIter<R> {
+    /// The next item without consuming it, an error is returned again by `next`
+    pub fn peek(&mut self) -> Option<Result<&RiffType, &RiffError>> {
+        let (iter, failed) = (&mut self.iter, &mut self.failed);
+        let (_, item) = self.peeked.get_or_insert_with(|| {
+            let position = ListIter::position(iter);
+            if *failed {
+                return (position, None);
+            }
+            let item = iter.next();
+            *failed = matches!(item, Some(Err(_)));
+            (position, item)
+        });
+        item.as_ref().map(Result::as_ref)
+    }
+
+    /// Consumes the next item only if it's read successfully and `func` accepts it
+    pub fn next_if(&mut self, func: impl FnOnce(&RiffType) -> bool) -> Option<RiffType> {
+        let position = PeekableListIter::position(self);
+        match self.next() {
+            Some(Ok(item)) if func(&item) => Some(item),
+            other => {
+                self.peeked = Some((position, other));
+                None
+            }
+        }
+    }
+
+    /// Byte offset of the header of the next item, including a peeked item
+    pub fn position(&self) -> u64 {
+        match self.peeked {
+            Some((position, _)) => position,
+            None => self.iter.position(),
+        }
+    }
+}
+
+impl<R: Read + Seek> Iterator for PeekableListIter<R> {
+    type Item = RiffResult<RiffType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((_, item)) = self.peeked.take() {
+            return item;
+        }
+        if self.failed {
+            return None;
+        }
+        let item = self.iter.next();
+        self.failed = matches!(item, Some(Err(_)));
+        item
+    }
+}
+
+/// Headers have no back pointers, so the first `next_back` scans the rest of the list
+/// headers forward and buffers them. Scanning stops at the first error, which is then
+/// the last item.
+impl<R: Read + Seek> DoubleEndedIterator for ListIter<R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.buffered.is_none() {
+            let mut buffered = VecDeque::new();
+            for item in self.by_ref() {
+                let failed = item.is_err();
+                buffered.push_back(item);
+                if failed {
+                    break;
+                }
+            }
+            self.next_position = self.list.end_position();
+            self.buffered = Some(buffered);
+        }
+        self.buffered.as_mut()?.pop_back()
+    }
+}
+
+/// Reads the data of a single chunk, returning EOF at the end of the chunk data.
+/// The reader is shared with the `RiffParser`, so every read seeks to where the previous read
+/// left off and other reads may be interleaved.
+pub struct ChunkReader<R> {
+    reader: Rc<RefCell<R>>,
+    position: u64,
+    end: u64,
+}
+
+impl<R> ChunkReader<R> {
+    /// Number of chunk data bytes not yet read
+    pub fn remaining(&self) -> u64 {
+        self.end - self.position
+    }
+}
+
+impl<R: Read + Seek> Read for ChunkReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf
+            .len()
+            .min(usize::try_from(self.remaining()).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(self.position))?;
+        let read = reader.read(&mut buf[..len])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+/// Probe whether `reader` is positioned at a `RIFF`, `RIFX` or `LIST` header without consuming it,
+/// the reader is seeked back to its original position before returning
+pub fn validate_riff_signature<R: Read + Seek>(reader: &mut R) -> io::Result<bool> {
+    let start = reader.stream_position()?;
+    let mut magic = [0u8; 4];
+    let result = reader.read_exact(&mut magic);
+    reader.seek(SeekFrom::Start(start))?;
+    match result {
+        Ok(()) => Ok(matches!(
+            Fourcc::new(magic),
+            tag::RIFF | tag::RIFX | tag::LIST
+        )),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+fn trim_nulls(data: &mut Vec<u8>) {
+    while data.last() == Some(&0) {
+        data.pop();
+    }
+}
+
+pub(crate) fn eof_error() -> RiffError {
+    RiffError::Io(io::Error::from(io::ErrorKind::UnexpectedEof))
+}
+
+/// A header read failed with `err`: running out of data or an unexpected magic is reported
+/// as `invalid`, e.g. `NotRiff` or a truncated header, any other I/O failure as `Io`
+pub(crate) fn header_error(err: BinError, invalid: RiffError) -> RiffError {
+    match io_failure(err) {
+        Some(err) => RiffError::Io(err),
+        None => invalid,
+    }
+}
+
+/// `header_error` for a header read straight from the reader
+pub(crate) fn header_io_error(err: io::Error, invalid: RiffError) -> RiffError {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        invalid
+    } else {
+        RiffError::Io(err)
+    }
+}
+
+/// The I/O error behind `err` other than the end of the stream, also from inside the
+/// variant errors of an enum like `HeaderType`
+fn io_failure(err: BinError) -> Option<io::Error> {
+    match err {
+        BinError::Io(err) if err.kind() != io::ErrorKind::UnexpectedEof => Some(err),
+        BinError::Backtrace(backtrace) => io_failure(*backtrace.error),
+        BinError::EnumErrors { variant_errors, .. } => variant_errors
+            .into_iter()
+            .find_map(|(_, err)| io_failure(err)),
+        _ => None,
+    }
+}
+
+pub(crate) fn missing_error(position: u64, tag: Fourcc) -> RiffError {
+    RiffError::MalformedChunk {
+        position,
+        message: format!("missing {}", tag),
+    }
+}
+
+/// Consume the next item if it's the list `id`, without consuming anything else
+pub(crate) fn next_list<R: Read + Seek>(
+    iter: &mut PeekableListIter<R>,
+    id: Fourcc,
+) -> RiffResult<Riff<List>> {
+    match iter.peek() {
+        Some(Ok(RiffType::List(list))) if list.id() == id => {
+            let list = *list;
+            iter.next();
+            Ok(list)
+        }
+        Some(Ok(_)) => Err(missing_error(PeekableListIter::position(iter), id)),
+        Some(Err(_)) | None => Err(iter.next().and_then(Result::err).unwrap_or_else(eof_error)),
+    }
+}
+
+/// Consume the next item if it's the chunk `id`, without consuming anything else
+pub(crate) fn next_chunk<R: Read + Seek>(
+    iter: &mut PeekableListIter<R>,
+    id: Fourcc,
+) -> RiffResult<Riff<Chunk>> {
+    match iter.peek() {
+        Some(Ok(RiffType::Chunk(chunk))) if chunk.id() == id => {
+            let chunk = *chunk;
+            iter.next();
+            Ok(chunk)
+        }
+        Some(Ok(_)) => Err(missing_error(PeekableListIter::position(iter), id)),
+        Some(Err(_)) | None => Err(iter.next().and_then(Result::err).unwrap_or_else(eof_error)),
+    }
+}
+
+pub(crate) fn validate_tag<H: Header>(riff: &Riff<H>, tag: Fourcc) -> RiffResult<()> {
+    if riff.id() != tag {
+        Err(missing_error(riff.position(), tag))
+    } else {
+        Ok(())
+    }
 }
@@ -0,0 +1,154 @@
+use crate::{fourcc::Fourcc, tag};
+use alloc::{vec, vec::Vec};
+use binrw::io::{self, Read, Seek, SeekFrom};
+
+/// Chunk ids recognised while scanning, besides AVI `movi` stream chunks
+const KNOWN: &[Fourcc] = &[
+    tag::RIFF,
+    tag::LIST,
+    tag::JUNK,
+    tag::PAD,
+    tag::AVIH,
+    tag::STRH,
+    tag::STRF,
+    tag::STRD,
+    tag::STRN,
+    tag::VPRP,
+    tag::DMLH,
+    tag::IDX1,
+    tag::FMT,
+    tag::DATA,
+    tag::FACT,
+    tag::CUE,
+    tag::VP8,
+    tag::VP8L,
+    tag::VP8X,
+    tag::ICCP,
+    tag::EXIF,
+    tag::XMP,
+    tag::ANIM,
+    tag::ALPH,
+    tag::ANIH,
+    tag::RATE,
+    tag::SEQ,
+    tag::ICON,
+];
+
+/// A header found by `RiffScanner`, its `size` comes from the file and may be wrong
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScannedChunk {
+    /// Byte offset of the header
+    pub offset: u64,
+    pub id: Fourcc,
+    pub size: u32,
+    /// The list type for `RIFF` and `LIST` headers
+    pub list_type: Option<Fourcc>,
+}
+
+/// Recovers chunk headers from damaged files by checking every byte offset for a known id,
+/// instead of following chunk sizes like `ListIter`.
+///
+/// Lists are descended into. Chunk data is skipped if the chunk fits in the stream,
+/// otherwise scanning resumes right after its header.
+pub struct RiffScanner<R> {
+    reader: R,
+    position: u64,
+    len: u64,
+    /// Data read ahead of `position`, starting at `buffer_start`
+    buffer: Vec<u8>,
+    buffer_start: u64,
+}
+
+impl<R: Read + Seek> RiffScanner<R> {
+    const BUFFER_SIZE: usize = 4096;
+
+    /// Scan `reader` from its start, iteration stops at the end of the stream or an IO error
+    pub fn scan(mut reader: R) -> impl Iterator<Item = ScannedChunk> {
+        let len = reader.seek(SeekFrom::End(0)).unwrap_or(0);
+        Self {
+            reader,
+            position: 0,
+            len,
+            buffer: Vec::new(),
+            buffer_start: 0,
+        }
+    }
+
+    /// Up to 12 bytes at `position`, refilling the buffer when they are not all buffered
+    fn peek(&mut self) -> io::Result<&[u8]> {
+        let wanted = (self.len - self.position).min(12) as usize;
+        let buffer_end = self.buffer_start + self.buffer.len() as u64;
+        if self.position < self.buffer_start || self.position + wanted as u64 > buffer_end {
+            let size = (self.len - self.position).min(Self::BUFFER_SIZE as u64) as usize;
+            self.buffer = vec![0; size];
+            self.buffer_start = self.position;
+            self.reader.seek(SeekFrom::Start(self.position))?;
+            self.reader.read_exact(&mut self.buffer)?;
+        }
+        let start = (self.position - self.buffer_start) as usize;
+        Ok(&self.buffer[start..start + wanted])
+    }
+
+    fn read_header(&mut self) -> io::Result<Option<ScannedChunk>> {
+        let offset = self.position;
+        let header = self.peek()?;
+        let id = Fourcc::new([header[0], header[1], header[2], header[3]]);
+        if !is_known(id) {
+            return Ok(None);
+        }
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let list_type = if id == tag::RIFF || id == tag::LIST {
+            let Some(list_type) = header.get(8..12) else {
+                return Ok(None);
+            };
+            Some(Fourcc::new([
+                list_type[0],
+                list_type[1],
+                list_type[2],
+                list_type[3],
+            ]))
+        } else {
+            None
+        };
+        Ok(Some(ScannedChunk {
+            offset,
+            id,
+            size,
+            list_type,
+        }))
+    }
+}
+
+impl<R: Read + Seek> Iterator for RiffScanner<R> {
+    type Item = ScannedChunk;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position + 8 <= self.len {
+            let Some(chunk) = self.read_header().ok()? else {
+                self.position += 1;
+                continue;
+            };
+            let data_end = chunk.offset + 8 + chunk.size as u64 + (chunk.size & 1) as u64;
+            self.position = if chunk.list_type.is_some() {
+                chunk.offset + 12
+            } else if data_end <= self.len {
+                data_end
+            } else {
+                chunk.offset + 8
+            };
+            return Some(chunk);
+        }
+        None
+    }
+}
+
+fn is_known(id: Fourcc) -> bool {
+    match id.bytes() {
+        // AVI stream data ##dc, ##db, ##wb, ##pc and OpenDML index ix##
+        [b'0'..=b'9', b'0'..=b'9', b'd', b'c' | b'b']
+        | [b'0'..=b'9', b'0'..=b'9', b'w', b'b']
+        | [b'0'..=b'9', b'0'..=b'9', b'p', b'c']
+        | [b'i', b'x', b'0'..=b'9', b'0'..=b'9'] => true,
+        _ => KNOWN.contains(&id),
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    Chunk, Riff, RiffParser, RiffResult, RiffType,
+    fourcc::Fourcc,
+    riff::{eof_error, validate_tag},
+};
+use alloc::vec::Vec;
+use binrw::{
+    BinRead,
+    io::{Read, Seek},
+};
+use core::fmt::Debug;
+
+pub mod tag {
+    use super::Fourcc;
+    pub use crate::tag::DATA;
+    pub const RMID: Fourcc = Fourcc::new(*b"RMID");
+}
+
+/// Standard MIDI File header chunk
+#[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(big, magic = b"MThd")]
+pub struct SmfHeader {
+    pub length: u32,
+    pub format: u16,
+    pub tracks: u16,
+    pub division: u16,
+}
+
+pub struct SmfParser<R> {
+    parser: RiffParser<R>,
+    pub header: SmfHeader,
+    pub data: Riff<Chunk>,
+    smf_data: Vec<u8>,
+}
+
+impl<R: Read + Seek> SmfParser<R> {
+    pub fn new(parser: RiffParser<R>) -> RiffResult<Self> {
+        let riff = parser.riff()?;
+        validate_tag(&riff, tag::RMID)?;
+
+        let data = parser
+            .chunks(riff)
+            .find_map(|result| match result {
+                Ok(RiffType::Chunk(data)) if data.id() == tag::DATA => Some(Ok(data)),
+                Err(e) => Some(Err(e)),
+                _ => None,
+            })
+            .ok_or_else(eof_error)??;
+        let header = parser.read_data_struct::<SmfHeader>(data)?;
+        let smf_data = parser.read_data_vec(data)?;
+
+        Ok(Self {
+            parser,
+            header,
+            data,
+            smf_data,
+        })
+    }
+
+    /// The complete SMF file contained in the `data` chunk
+    pub fn smf_data(&self) -> &[u8] {
+        &self.smf_data
+    }
+
+    pub fn riff_parser(&self) -> &RiffParser<R> {
+        &self.parser
+    }
+}
+
+impl<R: Read + Seek> Debug for SmfParser<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SmfParser")
+            .field("header", &self.header)
+            .finish()
+    }
+}
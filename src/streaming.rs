@@ -0,0 +1,204 @@
+use crate::{
+    Chunk, List, Riff, RiffError, RiffParser, RiffResult, RiffType, fourcc::Fourcc,
+    riff::header_io_error, tag,
+};
+use alloc::{vec, vec::Vec};
+use binrw::{Endian, io::Read};
+
+impl<R: Read> RiffParser<R> {
+    /// Parse a reader that can't seek, e.g. a UART or SPI flash stream.
+    /// See `StreamingRiffParser` for how it differs from `RiffParser`.
+    pub fn new_streaming(reader: R) -> StreamingRiffParser<R> {
+        StreamingRiffParser {
+            reader,
+            position: 0,
+            next_position: 0,
+            list_ends: Vec::new(),
+            endian: Endian::Little,
+        }
+    }
+}
+
+/// Forward only parser returning every list and chunk in document order.
+///
+/// Lists are descended into, the first item is the `RIFF` list itself.
+/// Chunk data can only be read before the next call to `next_chunk`,
+/// data that was not read is discarded when it is called.
+pub struct StreamingRiffParser<R> {
+    reader: R,
+    position: u64,
+    /// Where the next header starts, after the data of the last chunk or the type of the last list
+    next_position: u64,
+    /// End positions of the lists enclosing `position`, outermost first
+    list_ends: Vec<u64>,
+    /// Big for `RIFX` files, set when the `RIFF` header is read
+    endian: Endian,
+}
+
+impl<R: Read> StreamingRiffParser<R> {
+    /// Byte offset of the reader
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Byte order of the file, little endian unless it starts with a `RIFX` header
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    /// Return the next list or chunk header, or `None` at the end of the `RIFF` list
+    pub fn next_chunk(&mut self) -> RiffResult<Option<RiffType>> {
+        if self.position == 0 {
+            return self.read_riff().map(Some);
+        }
+        self.discard(self.next_position - self.position)?;
+        // Leave lists that have been fully read
+        while let Some(&end) = self.list_ends.last()
+            && self.position >= end
+        {
+            self.list_ends.pop();
+        }
+        let Some(&end) = self.list_ends.last() else {
+            return Ok(None);
+        };
+
+        let position = self.position;
+        let mut header = [0u8; 8];
+        self.read_header_bytes(&mut header)?;
+        let id = Fourcc::new([header[0], header[1], header[2], header[3]]);
+        let size = self.size(&header);
+        let item = if id == tag::LIST {
+            check_list_size(position, size)?;
+            let list = Riff::new(List::new(size, self.read_list_type()?), self.position);
+            RiffType::List(list)
+        } else if id == tag::RIFF || id == tag::RIFX {
+            return Err(RiffError::MalformedChunk {
+                position: self.position,
+                message: "nested RIFF header".into(),
+            });
+        } else {
+            RiffType::Chunk(Riff::new(Chunk::new(id, size), self.position))
+        };
+
+        let item_end = match item {
+            RiffType::List(list) => list.end_position(),
+            RiffType::Chunk(chunk) => chunk.end_position(),
+        };
+        if item_end > end {
+            return Err(RiffError::MalformedChunk {
+                position,
+                message: "chunk extends past end of list".into(),
+            });
+        }
+        self.next_position = match item {
+            RiffType::List(list) => {
+                self.list_ends.push(item_end);
+                list.position()
+            }
+            RiffType::Chunk(_) => item_end,
+        };
+        Ok(Some(item))
+    }
+
+    /// Read the data of `chunk`, which must be the chunk last returned by `next_chunk`
+    /// with none of its data read yet
+    pub fn read_data(&mut self, chunk: Riff<Chunk>, buffer: &mut [u8]) -> RiffResult<()> {
+        if chunk.position() != self.position {
+            return Err(RiffError::MalformedChunk {
+                position: chunk.position(),
+                message: "chunk already passed".into(),
+            });
+        }
+        if buffer.len() as u64 > chunk.data_size() as u64 {
+            return Err(RiffError::MalformedChunk {
+                position: chunk.position(),
+                message: "buffer too large".into(),
+            });
+        }
+        self.reader.read_exact(buffer)?;
+        self.position += buffer.len() as u64;
+        Ok(())
+    }
+
+    pub fn read_data_vec(&mut self, chunk: Riff<Chunk>) -> RiffResult<Vec<u8>> {
+        let mut buffer = vec![0u8; chunk.data_size() as usize];
+        self.read_data(chunk, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read_riff(&mut self) -> RiffResult<RiffType> {
+        let mut header = [0u8; 8];
+        self.reader
+            .read_exact(&mut header)
+            .map_err(|err| header_io_error(err, RiffError::NotRiff))?;
+        self.position = 8;
+        self.endian = match Fourcc::new([header[0], header[1], header[2], header[3]]) {
+            tag::RIFF => Endian::Little,
+            tag::RIFX => Endian::Big,
+            _ => return Err(RiffError::NotRiff),
+        };
+        let size = self.size(&header);
+        check_list_size(0, size)?;
+        let riff = Riff::new(List::new(size, self.read_list_type()?), self.position);
+        self.list_ends.push(riff.end_position());
+        self.next_position = self.position;
+        Ok(RiffType::List(riff))
+    }
+
+    /// The size field of a header in the byte order of the file
+    fn size(&self, header: &[u8; 8]) -> u32 {
+        let size = [header[4], header[5], header[6], header[7]];
+        match self.endian {
+            Endian::Big => u32::from_be_bytes(size),
+            Endian::Little => u32::from_le_bytes(size),
+        }
+    }
+
+    fn read_list_type(&mut self) -> RiffResult<Fourcc> {
+        let mut list_type = [0u8; 4];
+        self.read_header_bytes(&mut list_type)?;
+        Ok(Fourcc::new(list_type))
+    }
+
+    fn read_header_bytes(&mut self, buffer: &mut [u8]) -> RiffResult<()> {
+        let position = self.position;
+        self.reader.read_exact(buffer).map_err(|err| {
+            header_io_error(
+                err,
+                RiffError::MalformedChunk {
+                    position,
+                    message: "truncated chunk header".into(),
+                },
+            )
+        })?;
+        self.position += buffer.len() as u64;
+        Ok(())
+    }
+
+    /// Read and drop `count` bytes, e.g. chunk data that was not read
+    fn discard(&mut self, mut count: u64) -> RiffResult<()> {
+        let mut buffer = [0u8; 256];
+        while count > 0 {
+            let size = count.min(buffer.len() as u64) as usize;
+            self.reader.read_exact(&mut buffer[..size])?;
+            self.position += size as u64;
+            count -= size as u64;
+        }
+        Ok(())
+    }
+}
+
+/// The size of a list includes its type
+fn check_list_size(position: u64, size: u32) -> RiffResult<()> {
+    if size < 4 {
+        return Err(RiffError::MalformedChunk {
+            position,
+            message: "list too small for its type".into(),
+        });
+    }
+    Ok(())
+}
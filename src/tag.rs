@@ -0,0 +1,25 @@
+//! Well known fourccs of all the supported formats in one place
+
+use crate::fourcc::Fourcc;
+
+pub use crate::ani::tag::{ACON, ANIH, FRAM, ICON, RATE, SEQ};
+pub use crate::avi::tag::{
+    AUDS, AVI, AVIH, AVIX, DMLH, HDRL, IDX1, JUNK, MIDS, MOVI, ODML, REC, STRD, STRF, STRH, STRL,
+    STRN, TXTS, VIDS, VPRP,
+};
+pub use crate::fourcc::tag::{LIST, RIFF, RIFX};
+pub use crate::info::tag::{IART, ICMT, ICOP, ICRD, IDIT, INAM, ISFT};
+pub use crate::smf::tag::RMID;
+pub use crate::webp::tag::{ALPH, ANIM, EXIF, ICCP, VP8, VP8L, VP8X, WEBP, XMP};
+
+pub const INFO: Fourcc = Fourcc::new(*b"INFO");
+pub const PAD: Fourcc = Fourcc::new(*b"PAD ");
+
+pub const WAVE: Fourcc = Fourcc::new(*b"WAVE");
+pub const FMT: Fourcc = Fourcc::new(*b"fmt ");
+pub const DATA: Fourcc = Fourcc::new(*b"data");
+pub const FACT: Fourcc = Fourcc::new(*b"fact");
+pub const CUE: Fourcc = Fourcc::new(*b"cue ");
+/// Associated data list, naming `cue ` points
+pub const ADTL: Fourcc = Fourcc::new(*b"adtl");
+pub const LABL: Fourcc = Fourcc::new(*b"labl");
@@ -0,0 +1,97 @@
+use crate::{
+    HeaderType, RiffError, RiffResult,
+    avi::{WaveFormat, WaveFormatEx},
+    tag,
+};
+use alloc::vec::Vec;
+use binrw::{
+    BinWrite,
+    io::{Cursor, Seek, SeekFrom, Write},
+};
+
+/// Writes a `RIFF WAVE` file with a `fmt ` chunk followed by a `data` chunk of the samples.
+/// The sizes are unknown until `finalize`, which seeks back to write them.
+pub struct WavWriter<W> {
+    writer: W,
+    start: u64,
+    data_size_position: u64,
+    data_size: u32,
+    max_data_size: u32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    /// Start a PCM WAV file of `format` at the position of `writer`
+    pub fn new(writer: W, format: &WaveFormatEx) -> RiffResult<Self> {
+        Self::with_format(writer, &WaveFormat::Pcm(format.clone()))
+    }
+
+    /// Start a WAV file of any `format`, e.g. the MP3 audio of an AVI stream
+    pub fn with_format(mut writer: W, format: &WaveFormat) -> RiffResult<Self> {
+        let mut fmt = Cursor::new(Vec::new());
+        format.write(&mut fmt)?;
+        let fmt = fmt.into_inner();
+
+        let start = writer.stream_position()?;
+        HeaderType::riff(tag::WAVE, 0).write(&mut writer)?;
+        HeaderType::chunk(tag::FMT, fmt.len() as u32).write(&mut writer)?;
+        writer.write_all(&fmt)?;
+        if !fmt.len().is_multiple_of(2) {
+            writer.write_all(&[0])?;
+        }
+        let data_size_position = writer.stream_position()? + 4;
+        HeaderType::chunk(tag::DATA, 0).write(&mut writer)?;
+        // The RIFF size counts everything after itself, so the padded data
+        // must fit in what the headers leave of u32::MAX
+        let header_size = data_size_position + 4 - start - 8;
+        let max_data_size = (u32::MAX as u64).saturating_sub(header_size) as u32 & !1;
+        Ok(Self {
+            writer,
+            start,
+            data_size_position,
+            data_size: 0,
+            max_data_size,
+        })
+    }
+
+    /// Append sample data, which must be whole frames of the format
+    pub fn write_samples(&mut self, data: &[u8]) -> RiffResult<()> {
+        let Some(data_size) = u32::try_from(data.len())
+            .ok()
+            .and_then(|len| self.data_size.checked_add(len))
+            .filter(|data_size| *data_size <= self.max_data_size)
+        else {
+            return Err(RiffError::MalformedChunk {
+                position: self.start,
+                message: "WAV data exceeds 4GB".into(),
+            });
+        };
+        self.writer.write_all(data)?;
+        self.data_size = data_size;
+        Ok(())
+    }
+
+    /// Bytes of sample data written so far
+    pub fn data_size(&self) -> u32 {
+        self.data_size
+    }
+
+    /// Pad the data and back patch the `data` and `RIFF` sizes, returning the writer
+    /// positioned after the file
+    pub fn finalize(mut self) -> RiffResult<W> {
+        if !self.data_size.is_multiple_of(2) {
+            self.writer.write_all(&[0])?;
+        }
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.data_size_position))?;
+        self.writer.write_all(&self.data_size.to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(self.start + 4))?;
+        let riff_size =
+            u32::try_from(end - self.start - 8).map_err(|_| RiffError::MalformedChunk {
+                position: self.start,
+                message: "WAV file exceeds 4GB".into(),
+            })?;
+        self.writer.write_all(&riff_size.to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        Ok(self.writer)
+    }
+}
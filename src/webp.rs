@@ -0,0 +1,209 @@
+use crate::{
+    Chunk, Riff, RiffError, RiffParser, RiffResult, RiffType,
+    fourcc::Fourcc,
+    riff::{eof_error, missing_error, validate_tag},
+};
+use alloc::format;
+use binrw::{
+    BinRead,
+    io::{Read, Seek},
+};
+use core::fmt::Debug;
+
+pub mod tag {
+    use super::Fourcc;
+    pub const WEBP: Fourcc = Fourcc::new(*b"WEBP");
+    pub const VP8: Fourcc = Fourcc::new(*b"VP8 ");
+    pub const VP8L: Fourcc = Fourcc::new(*b"VP8L");
+    pub const VP8X: Fourcc = Fourcc::new(*b"VP8X");
+    pub const ICCP: Fourcc = Fourcc::new(*b"ICCP");
+    pub const EXIF: Fourcc = Fourcc::new(*b"EXIF");
+    pub const XMP: Fourcc = Fourcc::new(*b"XMP ");
+    pub const ANIM: Fourcc = Fourcc::new(*b"ANIM");
+    pub const ALPH: Fourcc = Fourcc::new(*b"ALPH");
+}
+
+fn u24(bytes: [u8; 3]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc6386#section-9.1
+// Only the keyframe header is parsed, the 14 bit dimensions have their scale bits masked off
+#[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct Vp8Header {
+    pub frame_tag: [u8; 3],
+    #[br(magic = b"\x9d\x01\x2a", map = |v: u16| v & 0x3fff)]
+    pub width: u16,
+    #[br(map = |v: u16| v & 0x3fff)]
+    pub height: u16,
+}
+
+/// https://developers.google.com/speed/webp/docs/webp_lossless_bitstream_specification#3_riff_header
+#[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little, magic = 0x2fu8)]
+pub struct Vp8lHeader {
+    bits: u32,
+}
+
+impl Vp8lHeader {
+    pub fn width(&self) -> u32 {
+        (self.bits & 0x3fff) + 1
+    }
+
+    pub fn height(&self) -> u32 {
+        ((self.bits >> 14) & 0x3fff) + 1
+    }
+
+    pub fn alpha_is_used(&self) -> bool {
+        (self.bits >> 28) & 1 != 0
+    }
+
+    pub fn version(&self) -> u8 {
+        (self.bits >> 29) as u8
+    }
+}
+
+/// https://developers.google.com/speed/webp/docs/riff_container#extended_file_format
+#[derive(BinRead, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[br(little)]
+pub struct Vp8xHeader {
+    pub flags: u8,
+    pub reserved: [u8; 3],
+    #[br(map = |v: [u8; 3]| u24(v) + 1)]
+    pub canvas_width: u32,
+    #[br(map = |v: [u8; 3]| u24(v) + 1)]
+    pub canvas_height: u32,
+}
+
+impl Vp8xHeader {
+    pub const FLAG_ANIMATION: u8 = 0x02;
+    pub const FLAG_XMP: u8 = 0x04;
+    pub const FLAG_EXIF: u8 = 0x08;
+    pub const FLAG_ALPHA: u8 = 0x10;
+    pub const FLAG_ICC: u8 = 0x20;
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WebpFormat {
+    Lossy(Vp8Header),
+    Lossless(Vp8lHeader),
+    Extended(Vp8xHeader),
+}
+
+pub struct WebpParser<R> {
+    parser: RiffParser<R>,
+    pub format: WebpFormat,
+    /// The `VP8 ` or `VP8L` chunk holding the image data, `None` for animations
+    pub bitstream: Option<Riff<Chunk>>,
+    pub iccp: Option<Riff<Chunk>>,
+    pub exif: Option<Riff<Chunk>>,
+    pub xmp: Option<Riff<Chunk>>,
+}
+
+impl<R: Read + Seek> WebpParser<R> {
+    pub fn new(parser: RiffParser<R>) -> RiffResult<Self> {
+        let riff = parser.riff()?;
+        validate_tag(&riff, tag::WEBP)?;
+
+        let mut webp_iter = parser.chunks(riff);
+        let RiffType::Chunk(first) = webp_iter.next().ok_or_else(eof_error)?? else {
+            return Err(missing_error(webp_iter.position(), tag::VP8X));
+        };
+
+        let mut bitstream = None;
+        let mut iccp = None;
+        let mut exif = None;
+        let mut xmp = None;
+        let format = match first.id() {
+            tag::VP8 => {
+                bitstream = Some(first);
+                WebpFormat::Lossy(parser.read_data_struct::<Vp8Header>(first)?)
+            }
+            tag::VP8L => {
+                bitstream = Some(first);
+                WebpFormat::Lossless(parser.read_data_struct::<Vp8lHeader>(first)?)
+            }
+            tag::VP8X => {
+                let header = parser.read_data_struct::<Vp8xHeader>(first)?;
+                for result in webp_iter {
+                    let RiffType::Chunk(chunk) = result? else {
+                        continue;
+                    };
+                    match chunk.id() {
+                        tag::ICCP => iccp = Some(chunk),
+                        tag::EXIF => exif = Some(chunk),
+                        tag::XMP => xmp = Some(chunk),
+                        tag::VP8 | tag::VP8L if bitstream.is_none() => bitstream = Some(chunk),
+                        _ => {}
+                    }
+                }
+                WebpFormat::Extended(header)
+            }
+            id => {
+                return Err(RiffError::MalformedChunk {
+                    position: first.position(),
+                    message: format!("unsupported {}", id),
+                });
+            }
+        };
+
+        Ok(Self {
+            parser,
+            format,
+            bitstream,
+            iccp,
+            exif,
+            xmp,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        match &self.format {
+            WebpFormat::Lossy(header) => header.width as u32,
+            WebpFormat::Lossless(header) => header.width(),
+            WebpFormat::Extended(header) => header.canvas_width,
+        }
+    }
+
+    pub fn height(&self) -> u32 {
+        match &self.format {
+            WebpFormat::Lossy(header) => header.height as u32,
+            WebpFormat::Lossless(header) => header.height(),
+            WebpFormat::Extended(header) => header.canvas_height,
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        match &self.format {
+            WebpFormat::Lossy(_) => false,
+            WebpFormat::Lossless(header) => header.alpha_is_used(),
+            WebpFormat::Extended(header) => header.flags & Vp8xHeader::FLAG_ALPHA != 0,
+        }
+    }
+
+    pub fn is_lossless(&self) -> bool {
+        self.bitstream
+            .is_some_and(|bitstream| bitstream.id() == tag::VP8L)
+    }
+
+    pub fn is_extended(&self) -> bool {
+        matches!(self.format, WebpFormat::Extended(_))
+    }
+
+    pub fn riff_parser(&self) -> &RiffParser<R> {
+        &self.parser
+    }
+}
+
+impl<R: Read + Seek> Debug for WebpParser<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WebpParser")
+            .field("format", &self.format)
+            .finish()
+    }
+}
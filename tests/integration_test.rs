@@ -1,13 +1,15 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 extern crate alloc;
-use alloc::{string::String, vec::Vec};
+use alloc::{format, string::String, vec, vec::Vec};
 use core::fmt::Debug;
 #[cfg(feature = "embedded-io")]
 use riffparse::EmbeddedAdapter;
 use riffparse::{
-    List, Read, Riff, RiffParser, RiffType, Seek, avi,
+    Chunk, ChunkFilter, HeaderType, List, Read, Riff, RiffError, RiffParser, RiffType, Seek,
+    SeekFrom, WalkControl, WalkEvent, ani, avi,
     binrw::io::{Cursor, Write},
+    builder, codec, diff, metrics, smf, tag, validate_riff_signature, webp,
 };
 
 // Generate test video:
@@ -18,133 +20,3036 @@ const TEST_AVI_SNAPSHOT: &str = include_str!("test.avi.snapshot");
 const TEST_AVI: &[u8] = include_bytes!("test.avi");
 const MP3_AVI_SNAPSHOT: &str = include_str!("mp3.avi.snapshot");
 const MP3_AVI: &[u8] = include_bytes!("mp3.avi");
+const RMID_SNAPSHOT: &str = include_str!("rmid.snapshot");
+
+fn build_chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 9);
+    chunk.extend_from_slice(id);
+    chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(data);
+    if !data.len().is_multiple_of(2) {
+        chunk.push(0);
+    }
+    chunk
+}
+
+fn build_list(magic: &[u8; 4], id: &[u8; 4], chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = id.to_vec();
+    for chunk in chunks {
+        data.extend_from_slice(chunk);
+    }
+    build_chunk(magic, &data)
+}
+
+fn build_rmid() -> Vec<u8> {
+    let mut smf = Vec::new();
+    // MThd format 0, 1 track, 96 ticks per quarter note
+    smf.extend_from_slice(b"MThd\0\0\0\x06\0\0\0\x01\0\x60");
+    // MTrk containing only end of track
+    smf.extend_from_slice(b"MTrk\0\0\0\x04\0\xff\x2f\0");
+    build_list(b"RIFF", b"RMID", &[build_chunk(b"data", &smf)])
+}
 
 fn debug<T: Debug, W: Write>(o: T, output: &mut W, indent: u8) {
     writeln!(output, "{:indent$}{o:?}", "", indent = indent as usize).unwrap();
 }
 
-fn process_list<R: Read + Seek + Debug, W: Write>(
-    parser: &RiffParser<R>,
-    list: Riff<List>,
-    output: &mut W,
-    mut indent: u8,
-) {
-    debug(list, output, indent);
-    indent += 4;
-
-    let mut stream: Option<avi::AviStreamHeader> = None;
-    for chunk in parser.chunks(list) {
-        let chunk = chunk.unwrap();
-        match chunk {
-            RiffType::List(riff_list) => {
-                process_list(parser, riff_list, output, indent);
-            }
-            RiffType::Chunk(riff_chunk) => {
-                debug(riff_chunk, output, indent);
-                match riff_chunk.id() {
-                    avi::tag::AVIH => {
-                        let avih = parser
-                            .read_data_struct::<avi::AviMainHeader>(riff_chunk)
-                            .unwrap();
-                        debug(avih, output, indent);
-                    }
-                    avi::tag::STRH => {
-                        let strh = parser
-                            .read_data_struct::<avi::AviStreamHeader>(riff_chunk)
-                            .unwrap();
-                        debug(&strh, output, indent);
-                        stream = Some(strh);
-                    }
-                    avi::tag::STRF => {
-                        if let Some(strh) = stream {
-                            match strh.fcc_type {
-                                avi::tag::VIDS => {
-                                    let vids = parser
-                                        .read_data_struct::<avi::BitmapInfo>(riff_chunk)
-                                        .unwrap();
-                                    debug(vids, output, indent);
-                                }
-                                avi::tag::AUDS => {
-                                    let auds = parser
-                                        .read_data_struct::<avi::WaveFormat>(riff_chunk)
-                                        .unwrap();
-                                    debug(auds, output, indent);
-                                }
-                                _ => {}
-                            };
-                            stream = None;
-                        }
-                    }
-                    _ => {}
-                }
-            }
+fn process_chunk<R: Read + Seek + Debug, W: Write>(
+    parser: &RiffParser<R>,
+    riff_chunk: Riff<Chunk>,
+    stream: &mut Option<avi::AviStreamHeader>,
+    output: &mut W,
+    indent: u8,
+) {
+    debug(riff_chunk, output, indent);
+    match riff_chunk.id() {
+        avi::tag::AVIH => {
+            let avih = parser
+                .read_data_struct::<avi::AviMainHeader>(riff_chunk)
+                .unwrap();
+            debug(avih, output, indent);
+        }
+        avi::tag::STRH => {
+            let strh = parser
+                .read_data_struct::<avi::AviStreamHeader>(riff_chunk)
+                .unwrap();
+            debug(&strh, output, indent);
+            *stream = Some(strh);
+        }
+        avi::tag::STRF => {
+            if let Some(strh) = stream.take() {
+                match strh.fcc_type {
+                    avi::tag::VIDS => {
+                        let vids = parser
+                            .read_data_struct::<avi::BitmapInfo>(riff_chunk)
+                            .unwrap();
+                        debug(vids, output, indent);
+                    }
+                    avi::tag::AUDS => {
+                        let auds = parser
+                            .read_data_struct::<avi::WaveFormat>(riff_chunk)
+                            .unwrap();
+                        debug(auds, output, indent);
+                    }
+                    _ => {}
+                };
+            }
+        }
+        _ => {}
+    }
+}
+
+fn dump_avi<R: Read + Seek + Debug, W: Write>(avi: R, output: &mut W) {
+    let parser = RiffParser::new(avi);
+    let mut indent = 0;
+    let mut stream = None;
+    parser
+        .depth_first_walk(|event| {
+            match event {
+                WalkEvent::Enter(list) => {
+                    debug(list, output, indent);
+                    indent += 4;
+                }
+                WalkEvent::Exit(_) => indent -= 4,
+                WalkEvent::Chunk(chunk) => {
+                    process_chunk(&parser, chunk, &mut stream, output, indent)
+                }
+            }
+            WalkControl::Continue
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_test_avi() {
+    let mut output = Vec::new();
+    dump_avi(Cursor::new(TEST_AVI), &mut output);
+    assert_eq!(TEST_AVI_SNAPSHOT, String::from_utf8(output).unwrap());
+}
+
+#[test]
+fn test_mp3_avi() {
+    let mut output = Vec::new();
+    dump_avi(Cursor::new(MP3_AVI), &mut output);
+    assert_eq!(MP3_AVI_SNAPSHOT, String::from_utf8(output).unwrap());
+}
+
+#[test]
+fn test_scanner() {
+    use riffparse::scanner::RiffScanner;
+
+    let mut data = TEST_AVI.to_vec();
+    // Corrupt the hdrl list size so the file can no longer be parsed
+    data[16..20].copy_from_slice(&0xffff_fff0u32.to_le_bytes());
+    assert!(avi::AviParser::new(RiffParser::new(Cursor::new(&data))).is_err());
+
+    let scanned: Vec<_> = RiffScanner::scan(Cursor::new(&data)).collect();
+    assert_eq!(scanned[0].id, tag::RIFF);
+    assert_eq!(scanned[0].list_type, Some(avi::tag::AVI));
+    assert_eq!(scanned[1].list_type, Some(avi::tag::HDRL));
+    assert!(
+        scanned
+            .iter()
+            .any(|chunk| chunk.list_type == Some(tag::MOVI))
+    );
+    let frames = scanned
+        .iter()
+        .filter(|chunk| chunk.id == avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED))
+        .count();
+    assert_eq!(frames, 20);
+    let last = scanned.last().unwrap();
+    assert_eq!((last.offset, last.id), (66160, tag::IDX1));
+}
+
+#[test]
+fn test_rmid() {
+    let mut output = Vec::new();
+    let rmid = build_rmid();
+    dump_avi(Cursor::new(&rmid), &mut output);
+    assert_eq!(RMID_SNAPSHOT, String::from_utf8(output).unwrap());
+
+    let smf_parser = smf::SmfParser::new(RiffParser::new(Cursor::new(&rmid))).unwrap();
+    assert_eq!(smf_parser.header.format, 0);
+    assert_eq!(smf_parser.header.tracks, 1);
+    assert_eq!(smf_parser.header.division, 96);
+    assert_eq!(smf_parser.smf_data(), &rmid[20..]);
+}
+
+#[test]
+fn test_ani() {
+    let mut anih = Vec::new();
+    for value in [36u32, 2, 3, 32, 32, 0, 0, 10, 0x3] {
+        anih.extend_from_slice(&value.to_le_bytes());
+    }
+    let steps: Vec<u8> = [0u32, 1, 0].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let data = build_list(
+        b"RIFF",
+        b"ACON",
+        &[
+            build_list(b"LIST", b"INFO", &[build_chunk(b"INAM", b"test\0")]),
+            build_chunk(b"anih", &anih),
+            build_chunk(b"seq ", &steps),
+            build_list(
+                b"LIST",
+                b"fram",
+                &[
+                    build_chunk(b"icon", b"first"),
+                    build_chunk(b"icon", b"second"),
+                ],
+            ),
+        ],
+    );
+    let ani_parser = ani::AniParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+    assert_eq!(ani_parser.ani_header.frames, 2);
+    assert_eq!(ani_parser.ani_header.steps, 3);
+    assert!(ani_parser.rates.is_none());
+    assert_eq!(ani_parser.sequence, Some(vec![0, 1, 0]));
+    assert_eq!(ani_parser.frame_data(0).unwrap(), b"first");
+    assert_eq!(ani_parser.frame_data(1).unwrap(), b"second");
+    assert!(ani_parser.frame_data(2).is_err());
+}
+
+#[test]
+fn test_from_bytes() {
+    let parser = RiffParser::from_bytes(TEST_AVI);
+    let avi_parser = avi::AviParser::new(parser).unwrap();
+    assert_eq!(avi_parser.stream_info.len(), 2);
+
+    let parser = RiffParser::from_vec(MP3_AVI.to_vec());
+    let avi_parser = avi::AviParser::new(parser).unwrap();
+    assert_eq!(avi_parser.stream_info.len(), 1);
+}
+
+#[test]
+fn test_rifx() {
+    use riffparse::binrw::Endian;
+
+    let mut rifx = b"RIFX\0\0\0\x1eWAVE".to_vec();
+    rifx.extend_from_slice(b"data\0\0\0\x04\0\0\x01\x02");
+    rifx.extend_from_slice(b"LIST\0\0\0\x06INFOab");
+    let parser = RiffParser::from_vec(rifx.clone());
+    let riff = parser.riff().unwrap();
+    assert_eq!(parser.endian(), Endian::Big);
+    assert_eq!(riff.id(), tag::WAVE);
+    assert_eq!(riff.data_size(), 26);
+    let items: Vec<_> = parser.chunks(riff).map(Result::unwrap).collect();
+    let [RiffType::Chunk(data), RiffType::List(info)] = items[..] else {
+        panic!("expected data chunk and INFO list");
+    };
+    assert_eq!(data.id(), tag::DATA);
+    assert_eq!(parser.read_data_struct::<u32>(data).unwrap(), 0x0102);
+    assert_eq!(info.id(), tag::INFO);
+    assert_eq!(info.data_size(), 2);
+    assert!(validate_riff_signature(&mut Cursor::new(&rifx)).unwrap());
+
+    let mut header = Cursor::new(Vec::new());
+    HeaderType::rifx(tag::WAVE, 0x1e)
+        .write_endian(&mut header, Endian::Big)
+        .unwrap();
+    assert_eq!(header.into_inner(), rifx[..12]);
+
+    let mut streaming = RiffParser::new_streaming(&rifx[..]);
+    let Some(RiffType::List(riff)) = streaming.next_chunk().unwrap() else {
+        panic!("expected RIFX list");
+    };
+    assert_eq!(streaming.endian(), Endian::Big);
+    assert_eq!(riff.data_size(), 26);
+    let Some(RiffType::Chunk(data)) = streaming.next_chunk().unwrap() else {
+        panic!("expected data chunk");
+    };
+    assert_eq!(streaming.read_data_vec(data).unwrap(), [0, 0, 1, 2]);
+    let Some(RiffType::List(info)) = streaming.next_chunk().unwrap() else {
+        panic!("expected INFO list");
+    };
+    assert_eq!(info.data_size(), 2);
+
+    let parser = RiffParser::from_bytes(TEST_AVI);
+    parser.riff().unwrap();
+    assert_eq!(parser.endian(), Endian::Little);
+}
+
+#[test]
+fn test_header_type() {
+    let mut output = Cursor::new(Vec::new());
+    HeaderType::riff(tag::WAVE, 4 + 8 + 2)
+        .write(&mut output)
+        .unwrap();
+    HeaderType::chunk(tag::DATA, 2).write(&mut output).unwrap();
+    output.write_all(&[1, 2]).unwrap();
+    let output = output.into_inner();
+    assert_eq!(
+        output,
+        build_list(b"RIFF", b"WAVE", &[build_chunk(b"data", &[1, 2])])
+    );
+
+    let mut list = Cursor::new(Vec::new());
+    HeaderType::list(tag::INFO, 4).write(&mut list).unwrap();
+    assert_eq!(list.into_inner(), b"LIST\x04\0\0\0INFO");
+}
+
+#[test]
+fn test_builder() {
+    let mut info = builder::ListBuilder::new();
+    info.add_chunk(tag::ISFT, b"riffparse\0");
+    let mut riff = builder::RiffBuilder::new(tag::WAVE);
+    riff.add_chunk(tag::FMT, b"odd")
+        .add_list(tag::INFO, info)
+        .add_chunk(tag::DATA, &[]);
+    let mut output = Cursor::new(Vec::new());
+    riff.build(&mut output).unwrap();
+    let output = output.into_inner();
+
+    let expected = build_list(
+        b"RIFF",
+        b"WAVE",
+        &[
+            build_chunk(b"fmt ", b"odd"),
+            build_list(b"LIST", b"INFO", &[build_chunk(b"ISFT", b"riffparse\0")]),
+            build_chunk(b"data", &[]),
+        ],
+    );
+    assert_eq!(output, expected);
+    assert_eq!(riff.total_size(), expected.len() as u64);
+
+    let parser = RiffParser::from_vec(output);
+    let root = parser.riff().unwrap();
+    assert_eq!(root.id(), tag::WAVE);
+    assert_eq!(parser.chunk_count(root).unwrap(), 3);
+}
+
+#[test]
+fn test_diff() {
+    use riffparse::fourcc::Fourcc;
+
+    fn build(frames: &[&[u8]], extra: bool) -> Vec<u8> {
+        let mut hdrl = builder::ListBuilder::new();
+        hdrl.add_chunk(tag::AVIH, &[0; 56]);
+        let mut movi = builder::ListBuilder::new();
+        for frame in frames {
+            movi.add_chunk(Fourcc::new(*b"00dc"), frame);
+        }
+        let mut riff = builder::RiffBuilder::new(tag::AVI);
+        riff.add_list(tag::HDRL, hdrl).add_list(tag::MOVI, movi);
+        if extra {
+            riff.add_chunk(tag::JUNK, &[0; 4]);
+        }
+        let mut output = Cursor::new(Vec::new());
+        riff.build(&mut output).unwrap();
+        output.into_inner()
+    }
+
+    let original = build(&[b"one", b"two", b"three"], false);
+    let options = diff::DiffOptions::default();
+    let diffs = diff::diff_riff(Cursor::new(&original), Cursor::new(&original), &options).unwrap();
+    assert!(diffs.is_empty());
+
+    let modified = build(&[b"one", b"TWO", b"three!", b"four"], true);
+    let diffs = diff::diff_riff(Cursor::new(&original), Cursor::new(&modified), &options).unwrap();
+    assert_eq!(diffs.len(), 3);
+    assert!(matches!(
+        &diffs[0],
+        diff::ChunkDiff::SizeChanged { path, a, b }
+            if path[1..] == [tag::MOVI, Fourcc::new(*b"00dc")]
+                && a.data_size() == 5
+                && b.data_size() == 6
+    ));
+    assert!(
+        matches!(&diffs[1], diff::ChunkDiff::Added { item, .. } if item.id() == Fourcc::new(*b"00dc"))
+    );
+    assert!(
+        matches!(&diffs[2], diff::ChunkDiff::Added { path, .. } if path == &[tag::AVI, tag::JUNK])
+    );
+
+    let options = diff::DiffOptions { compare_data: true };
+    let diffs = diff::diff_riff(Cursor::new(&original), Cursor::new(&modified), &options).unwrap();
+    assert_eq!(diffs.len(), 4);
+    assert!(matches!(&diffs[0], diff::ChunkDiff::DataChanged { a, .. } if a.data_size() == 3));
+    let diffs = diff::diff_riff(Cursor::new(&modified), Cursor::new(&original), &options).unwrap();
+    assert!(
+        matches!(&diffs[2], diff::ChunkDiff::Removed { item, .. } if item.id() == Fourcc::new(*b"00dc"))
+    );
+    assert!(matches!(&diffs[3], diff::ChunkDiff::Removed { item, .. } if item.id() == tag::JUNK));
+}
+
+#[test]
+fn test_streaming() {
+    use riffparse::fourcc::Fourcc;
+
+    fn walk<R: Read + Seek>(parser: &RiffParser<R>, list: Riff<List>, ids: &mut Vec<Fourcc>) {
+        for item in parser.chunks(list) {
+            let item = item.unwrap();
+            ids.push(item.id());
+            if let RiffType::List(list) = item {
+                walk(parser, list, ids);
+            }
+        }
+    }
+    let parser = RiffParser::from_bytes(TEST_AVI);
+    let riff = parser.riff().unwrap();
+    let mut expected = vec![riff.id()];
+    walk(&parser, riff, &mut expected);
+
+    let mut streaming = RiffParser::new_streaming(TEST_AVI);
+    let mut ids = Vec::new();
+    let mut first_frame = None;
+    while let Some(item) = streaming.next_chunk().unwrap() {
+        ids.push(item.id());
+        if let RiffType::Chunk(chunk) = item
+            && chunk.id() == Fourcc::new(*b"00dc")
+            && first_frame.is_none()
+        {
+            first_frame = Some(streaming.read_data_vec(chunk).unwrap());
+            // The data has been consumed
+            assert!(streaming.read_data_vec(chunk).is_err());
+        }
+    }
+    assert_eq!(ids, expected);
+    assert_eq!(first_frame.unwrap().len(), 1267);
+    assert_eq!(streaming.position(), TEST_AVI.len() as u64);
+
+    let mut streaming = RiffParser::new_streaming(&b"JUNK\0\0\0\0"[..]);
+    assert!(matches!(streaming.next_chunk(), Err(RiffError::NotRiff)));
+}
+
+#[test]
+fn test_chunk_stats() {
+    let parser = RiffParser::from_bytes(TEST_AVI);
+    let stats = metrics::ChunkStats::collect(&parser).unwrap();
+    assert_eq!(stats.chunk_count, 47);
+    assert_eq!(stats.list_count, 5);
+    assert_eq!(stats.max_depth, 3);
+    assert_eq!(stats.largest_chunk_size, Some(4120));
+    assert_eq!(stats.smallest_chunk_size, Some(13));
+    assert!((stats.mean_chunk_size.unwrap() - 1409.91).abs() < 0.01);
+    assert_eq!(stats.median_chunk_size, Some(1259.0));
+    assert_eq!(
+        stats
+            .chunk_ids
+            .get(avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED)),
+        Some(&20)
+    );
+    assert_eq!(stats.chunk_ids.get(tag::JUNK), Some(&4));
+    assert_eq!(
+        stats
+            .chunk_ids
+            .iter()
+            .map(|(_, count)| count)
+            .sum::<usize>(),
+        47
+    );
+}
+
+#[test]
+fn test_cross_reader() {
+    let parser1 = RiffParser::new(Cursor::new(TEST_AVI));
+    let riff = parser1.riff().unwrap();
+    let mut chunks = parser1.chunks(riff);
+    chunks.next().unwrap().unwrap();
+    chunks.next().unwrap().unwrap();
+    let chunk = chunks.next().unwrap().unwrap();
+    let parser2 = RiffParser::new(Cursor::new(TEST_AVI));
+    match chunk {
+        RiffType::List(chunk) => {
+            assert_eq!(
+                parser1.read_data_vec(chunk).unwrap(),
+                parser2.read_data_vec(chunk).unwrap()
+            );
+        }
+        RiffType::Chunk(chunk) => {
+            assert_eq!(
+                parser1.read_data_vec(chunk).unwrap(),
+                parser2.read_data_vec(chunk).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_list_iter_position() {
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let riff = parser.riff().unwrap();
+    let mut chunks = parser.chunks(riff);
+    let mut position = chunks.position();
+    assert_eq!(position, riff.position());
+    while let Some(chunk) = chunks.next() {
+        chunk.unwrap();
+        assert!(chunks.position() > position);
+        position = chunks.position();
+    }
+}
+
+#[test]
+fn test_empty_chunk() {
+    let data = b"RIFF\x22\0\0\0TESTmark\0\0\0\0LIST\x04\0\0\0emptdata\x01\0\0\0x\0";
+    let parser = RiffParser::new(Cursor::new(&data[..]));
+    let riff = parser.riff().unwrap();
+    assert!(!riff.is_empty());
+    let items: Vec<_> = parser.chunks(riff).map(Result::unwrap).collect();
+    let [
+        RiffType::Chunk(mark),
+        RiffType::List(list),
+        RiffType::Chunk(data),
+    ] = items[..]
+    else {
+        panic!("unexpected items {items:?}");
+    };
+    assert!(mark.is_empty());
+    assert!(!mark.has_data());
+    assert!(parser.read_data_vec(mark).unwrap().is_empty());
+    assert!(list.is_empty());
+    assert_eq!(parser.chunks(list).count(), 0);
+    assert!(!data.is_empty());
+    assert!(data.has_data());
+    assert_eq!(parser.read_data_vec(data).unwrap(), b"x");
+}
+
+#[test]
+fn test_peekable_list_iter() {
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let riff = parser.riff().unwrap();
+    let mut chunks = parser.chunks(riff).peekable_chunk();
+    let position = chunks.position();
+    assert_eq!(chunks.peek().unwrap().unwrap().id(), avi::tag::HDRL);
+    assert_eq!(chunks.peek().unwrap().unwrap().id(), avi::tag::HDRL);
+    assert_eq!(chunks.position(), position);
+    assert!(chunks.next_if(|item| item.id() == avi::tag::MOVI).is_none());
+    assert_eq!(chunks.position(), position);
+    let hdrl = chunks.next_if(|item| item.id() == avi::tag::HDRL).unwrap();
+    assert!(matches!(hdrl, RiffType::List(_)));
+    assert!(chunks.position() > position);
+    assert_eq!(chunks.next().unwrap().unwrap().id(), riffparse::tag::INFO);
+    let ids: Vec<_> = chunks.map(|item| item.unwrap().id()).collect();
+    assert_eq!(ids, [avi::tag::JUNK, avi::tag::MOVI, avi::tag::IDX1]);
+
+    let mut data = TEST_AVI.to_vec();
+    data.truncate(14);
+    let parser = RiffParser::new(Cursor::new(data));
+    let mut chunks = parser.chunks(parser.riff().unwrap()).peekable_chunk();
+    assert!(chunks.peek().unwrap().is_err());
+    assert!(chunks.next_if(|_| true).is_none());
+    assert!(chunks.next().unwrap().is_err());
+    assert!(chunks.next().is_none());
+}
+
+#[test]
+fn test_chunk_count() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let parser = avi_parser.riff_parser();
+    assert_eq!(parser.chunk_count(avi_parser.movi).unwrap(), 35);
+    let riff = parser.riff_at(0).unwrap();
+    // hdrl, INFO, JUNK, movi, idx1
+    assert_eq!(parser.chunk_count(riff).unwrap(), 5);
+
+    let mut data = TEST_AVI.to_vec();
+    data.truncate(20000);
+    let parser = RiffParser::new(Cursor::new(data));
+    assert!(parser.chunk_count(avi_parser.movi).is_err());
+}
+
+#[test]
+fn test_read_data_string() {
+    let data = build_list(
+        b"RIFF",
+        b"WAVE",
+        &[
+            build_chunk(b"INAM", b"caf\xc3\xa9\0"),
+            build_chunk(b"IART", b"caf\xe9"),
+        ],
+    );
+    let parser = RiffParser::from_vec(data);
+    let riff = parser.riff().unwrap();
+    let chunks: Vec<_> = parser.chunks(riff).map(Result::unwrap).collect();
+    let [RiffType::Chunk(inam), RiffType::Chunk(iart)] = chunks[..] else {
+        panic!("expected INAM and IART chunks");
+    };
+    assert_eq!(parser.read_data_string(inam).unwrap(), "caf\u{e9}");
+    assert!(matches!(
+        parser.read_data_string(iart),
+        Err(RiffError::MalformedChunk { .. })
+    ));
+    assert_eq!(parser.read_data_string_lossy(iart).unwrap(), "caf\u{fffd}");
+}
+
+#[test]
+fn test_depth_first_walk() {
+    let parser = RiffParser::from_bytes(TEST_AVI);
+    let mut ids = Vec::new();
+    parser
+        .depth_first_walk(|event| match event {
+            WalkEvent::Enter(list) if list.id() == avi::tag::HDRL => WalkControl::Skip,
+            WalkEvent::Enter(list) => {
+                ids.push(list.id());
+                WalkControl::Continue
+            }
+            WalkEvent::Exit(list) => {
+                ids.push(list.id());
+                WalkControl::Continue
+            }
+            WalkEvent::Chunk(chunk) if chunk.id() == avi::tag::stream(1, avi::tag::DATA_AUDIO) => {
+                WalkControl::Abort
+            }
+            WalkEvent::Chunk(chunk) => {
+                ids.push(chunk.id());
+                WalkControl::Continue
+            }
+        })
+        .unwrap();
+    let video = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    assert_eq!(
+        ids,
+        [
+            avi::tag::AVI,
+            avi::tag::HDRL,
+            tag::INFO,
+            tag::ISFT,
+            tag::INFO,
+            avi::tag::JUNK,
+            avi::tag::MOVI,
+            video,
+        ]
+    );
+}
+
+#[test]
+fn test_header_position() {
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let riff = parser.riff().unwrap();
+    assert_eq!((riff.header_position(), riff.data_position()), (0, 12));
+    let hdrl = parser.find_list(riff, avi::tag::HDRL).unwrap().unwrap();
+    assert_eq!((hdrl.header_position(), hdrl.data_position()), (12, 24));
+    let avih = parser.find_chunk(hdrl, avi::tag::AVIH).unwrap().unwrap();
+    assert_eq!((avih.header_position(), avih.data_position()), (24, 32));
+    assert_eq!(avih.data_position(), avih.position());
+}
+
+#[test]
+fn test_contextual_parser() {
+    let parser = RiffParser::new(Cursor::new(TEST_AVI)).with_context("test.avi");
+    let riff = parser.riff().unwrap();
+    assert_eq!(parser.chunks(riff).count(), 5);
+    let hdrl = parser.find_list(riff, avi::tag::HDRL).unwrap().unwrap();
+    let avih = parser.find_chunk(hdrl, avi::tag::AVIH).unwrap().unwrap();
+    assert_eq!(parser.read_data_vec(avih).unwrap().len(), 56);
+
+    let error = RiffParser::new(Cursor::new(b"JUNK"))
+        .with_context("junk.bin")
+        .riff()
+        .unwrap_err();
+    assert!(matches!(error.inner, RiffError::NotRiff));
+    assert_eq!(error.path.as_deref(), Some("junk.bin"));
+    assert_eq!(format!("{error}"), "junk.bin: at 0x0: invalid RIFF file");
+
+    // Truncated inside the avih data
+    let parser = RiffParser::new(Cursor::new(&TEST_AVI[..40])).with_context("short.avi");
+    let riff = parser.riff().unwrap();
+    let hdrl = parser.find_list(riff, avi::tag::HDRL).unwrap().unwrap();
+    let avih = parser.find_chunk(hdrl, avi::tag::AVIH).unwrap().unwrap();
+    let error = parser.read_data_vec(avih).unwrap_err();
+    assert!(matches!(error.inner, RiffError::Io(_)));
+    assert_eq!(error.offset, 32);
+}
+
+#[test]
+fn test_byte_range() {
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let riff = parser.riff().unwrap();
+    assert_eq!(riff.full_byte_range(), 0..TEST_AVI.len() as u64);
+    let movi = parser.find_list(riff, tag::MOVI).unwrap().unwrap();
+    assert_eq!(movi.byte_range(), 9982..9982 + 56178);
+    assert_eq!(movi.full_byte_range(), 9970..66160);
+    let idx1 = parser.find_chunk(riff, tag::IDX1).unwrap().unwrap();
+    assert!(!movi.contains_offset(idx1.header_position()));
+    for item in parser.chunks(movi) {
+        let RiffType::Chunk(chunk) = item.unwrap() else {
+            continue;
+        };
+        assert!(movi.contains_offset(chunk.header_position()));
+        assert_eq!(
+            chunk.full_byte_range().end - chunk.full_byte_range().start,
+            chunk.total_size_on_disk()
+        );
+    }
+}
+
+#[test]
+fn test_top_level() {
+    use riffparse::fourcc::Fourcc;
+
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let top = parser.top_level().unwrap();
+    assert!(matches!(top, RiffType::List(list) if list.id() == avi::tag::AVI));
+    let riff = parser.assert_is_riff(top).unwrap();
+    assert_eq!(parser.chunks(riff).count(), 5);
+
+    let info = build_list(b"LIST", b"INFO", &[build_chunk(b"INAM", b"name\0")]);
+    let parser = RiffParser::from_vec(info.clone());
+    assert!(matches!(parser.riff(), Err(RiffError::NotRiff)));
+    let parser = RiffParser::from_vec(info);
+    let RiffType::List(list) = parser.top_level().unwrap() else {
+        panic!("LIST is a list");
+    };
+    assert_eq!(list.id(), tag::INFO);
+    let inam = parser
+        .find_chunk(list, Fourcc::new(*b"INAM"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(parser.read_data_string(inam).unwrap(), "name");
+    assert!(matches!(
+        parser.assert_is_riff(RiffType::List(list)),
+        Err(RiffError::NotRiff)
+    ));
+
+    let parser = RiffParser::from_vec(build_chunk(b"data", b"1234"));
+    let top = parser.top_level().unwrap();
+    assert!(matches!(top, RiffType::Chunk(chunk) if chunk.id() == Fourcc::new(*b"data")));
+    assert!(matches!(
+        parser.assert_is_riff(top),
+        Err(RiffError::NotRiff)
+    ));
+    assert!(matches!(
+        RiffParser::new(Cursor::new(b"RIF")).top_level(),
+        Err(RiffError::NotRiff)
+    ));
+}
+
+#[test]
+fn test_find_chunk() {
+    let parser = RiffParser::from_bytes(TEST_AVI);
+    let riff = parser.riff().unwrap();
+    let hdrl = parser.find_list(riff, avi::tag::HDRL).unwrap().unwrap();
+    let avih = parser.find_chunk(hdrl, avi::tag::AVIH).unwrap().unwrap();
+    assert_eq!(avih.data_size(), 56);
+    assert!(parser.find_chunk(hdrl, avi::tag::STRH).unwrap().is_none());
+    // Lists and chunks are told apart
+    assert!(parser.find_chunk(riff, avi::tag::MOVI).unwrap().is_none());
+    assert!(parser.find_list(riff, avi::tag::IDX1).unwrap().is_none());
+    assert!(parser.find_chunk(riff, avi::tag::IDX1).unwrap().is_some());
+}
+
+#[test]
+fn test_list_iter_rev() {
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let riff = parser.riff().unwrap();
+    let ids = |iter: &mut dyn Iterator<Item = riffparse::RiffResult<RiffType>>| -> Vec<_> {
+        iter.map(|item| item.unwrap().id()).collect()
+    };
+    let mut forward = ids(&mut parser.chunks(riff));
+    forward.reverse();
+    assert_eq!(ids(&mut parser.chunks(riff).rev()), forward);
+
+    let mut chunks = parser.chunks(riff);
+    let first = chunks.next().unwrap().unwrap().id();
+    let last = chunks.next_back().unwrap().unwrap().id();
+    assert_eq!(first, tag::HDRL);
+    assert_eq!(last, tag::IDX1);
+    assert_eq!(chunks.count(), forward.len() - 2);
+}
+
+#[test]
+fn test_list_iter_size_hint() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let parser = avi_parser.riff_parser();
+    let mut chunks = parser.chunks(avi_parser.movi);
+    let (lower, Some(upper)) = chunks.size_hint() else {
+        panic!("no upper bound");
+    };
+    assert_eq!(lower, 0);
+    assert!(upper >= parser.chunks(avi_parser.movi).count());
+    chunks.by_ref().for_each(drop);
+    assert_eq!(chunks.size_hint(), (0, Some(0)));
+}
+
+#[test]
+fn test_read_data_partial() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let parser = avi_parser.riff_parser();
+    let stream_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    let chunk = avi_parser.movi_chunks(stream_id).next().unwrap().unwrap();
+    let data = parser.read_data_vec(chunk).unwrap();
+    // 1267 bytes plus a pad byte
+    assert_eq!(chunk.total_size_on_disk(), 8 + 1267 + 1);
+
+    let mut buffer = [0u8; 16];
+    parser.read_data_partial(chunk, 100, &mut buffer).unwrap();
+    assert_eq!(buffer, data[100..116]);
+    parser
+        .read_data_partial(chunk, chunk.data_size() - 16, &mut buffer)
+        .unwrap();
+    assert_eq!(buffer, data[data.len() - 16..]);
+    assert!(
+        parser
+            .read_data_partial(chunk, chunk.data_size() - 15, &mut buffer)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_validate_riff_signature() {
+    let mut reader = Cursor::new(TEST_AVI);
+    assert!(validate_riff_signature(&mut reader).unwrap());
+    assert_eq!(reader.stream_position().unwrap(), 0);
+    // hdrl LIST header
+    reader.seek(riffparse::SeekFrom::Start(12)).unwrap();
+    assert!(validate_riff_signature(&mut reader).unwrap());
+    assert_eq!(reader.stream_position().unwrap(), 12);
+
+    assert!(!validate_riff_signature(&mut Cursor::new(&b"RIF"[..])).unwrap());
+    assert!(!validate_riff_signature(&mut Cursor::new(&b"MThd"[..])).unwrap());
+}
+
+#[test]
+fn test_read_data_struct_at() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let parser = avi_parser.riff_parser();
+    let idx1 = avi_parser.idx1.unwrap();
+    let second = parser
+        .read_data_struct_at::<avi::AviIndexEntry>(idx1, 16)
+        .unwrap();
+    assert_eq!(second.chunk_id, avi::tag::stream(1, avi::tag::DATA_AUDIO));
+    assert_eq!(second.offset, 1280);
+    assert!(
+        parser
+            .read_data_struct_at::<avi::AviIndexEntry>(idx1, idx1.data_size() - 8)
+            .is_err()
+    );
+    assert!(
+        parser
+            .read_data_struct_at::<avi::AviIndexEntry>(idx1, idx1.data_size() + 1)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_read_data_struct_with_args() {
+    use riffparse::binrw::VecArgs;
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let parser = avi_parser.riff_parser();
+    let idx1 = avi_parser.idx1.unwrap();
+    let second = parser
+        .read_data_struct_with_args::<Vec<u32>>(
+            idx1,
+            16,
+            VecArgs {
+                count: 4,
+                inner: (),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        second[0],
+        u32::from(avi::tag::stream(1, avi::tag::DATA_AUDIO))
+    );
+    assert_eq!(second[2], 1280);
+    assert_eq!(
+        parser
+            .read_data_struct_with_args::<Vec<u8>>(
+                idx1,
+                0,
+                VecArgs {
+                    count: 560,
+                    inner: ()
+                }
+            )
+            .unwrap(),
+        parser.read_data_vec(idx1).unwrap()
+    );
+    assert!(
+        parser
+            .read_data_struct_with_args::<Vec<u8>>(
+                idx1,
+                0,
+                VecArgs {
+                    count: 561,
+                    inner: ()
+                }
+            )
+            .is_err()
+    );
+}
+
+#[test]
+fn test_limited_reader() {
+    use riffparse::io::LimitedReader;
+
+    let mut reader = LimitedReader::new(Cursor::new(TEST_AVI), 12);
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header).unwrap();
+    assert_eq!(&header[..4], b"RIFF");
+    let mut buffer = [0u8; 8];
+    assert_eq!(reader.read(&mut buffer).unwrap(), 4);
+    assert_eq!(&buffer[..4], b"AVI ");
+    assert_eq!(reader.remaining(), 0);
+    assert!(reader.read(&mut buffer).is_err());
+    assert_eq!(reader.into_inner().stream_position().unwrap(), 12);
+}
+
+#[test]
+fn test_counting_reader() {
+    use riffparse::io::CountingReader;
+
+    let mut streaming = RiffParser::new_streaming(CountingReader::new(TEST_AVI));
+    while streaming.next_chunk().unwrap().is_some() {}
+    assert_eq!(streaming.into_inner().bytes_read(), TEST_AVI.len() as u64);
+
+    let mut reader = CountingReader::new(Cursor::new(TEST_AVI));
+    reader.seek(SeekFrom::Start(8)).unwrap();
+    let mut form_type = [0u8; 4];
+    reader.read_exact(&mut form_type).unwrap();
+    assert_eq!(&form_type, b"AVI ");
+    assert_eq!(reader.bytes_read(), 4);
+}
+
+#[test]
+fn test_multi_reader() {
+    use riffparse::io::MultiReader;
+
+    // Split inside headers and chunk data
+    let segments = [
+        (0, &TEST_AVI[..30]),
+        (30, &TEST_AVI[30..9001]),
+        (9001, &TEST_AVI[9001..]),
+    ];
+    let reader = MultiReader::new(&segments);
+    assert_eq!(reader.len(), TEST_AVI.len() as u64);
+    let avi_parser = avi::AviParser::new(RiffParser::new(reader)).unwrap();
+    let avi_reference = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let stream_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    for (chunk, reference) in avi_parser
+        .movi_chunks(stream_id)
+        .zip(avi_reference.movi_chunks(stream_id))
+    {
+        assert_eq!(
+            avi_parser
+                .riff_parser()
+                .read_data_vec(chunk.unwrap())
+                .unwrap(),
+            avi_reference
+                .riff_parser()
+                .read_data_vec(reference.unwrap())
+                .unwrap()
+        );
+    }
+
+    let segments = [(0, &b"RIFF"[..]), (8, &b"AVI "[..])];
+    let mut reader = MultiReader::new(&segments);
+    let mut buffer = [0u8; 8];
+    assert_eq!(
+        reader.read_exact(&mut buffer).unwrap_err().kind(),
+        riffparse::binrw::io::ErrorKind::UnexpectedEof
+    );
+    assert_eq!(reader.seek(SeekFrom::End(-4)).unwrap(), 8);
+    reader.read_exact(&mut buffer[..4]).unwrap();
+    assert_eq!(&buffer[..4], b"AVI ");
+    assert_eq!(reader.read(&mut buffer).unwrap(), 0);
+    assert!(reader.seek(SeekFrom::Current(-20)).is_err());
+}
+
+#[test]
+fn test_offset_reader() {
+    use riffparse::io::OffsetReader;
+
+    let mut embedded = vec![0xaa; 100];
+    embedded.extend_from_slice(TEST_AVI);
+    let reader = OffsetReader::new(Cursor::new(embedded.as_slice()), 100).unwrap();
+    let avi_parser = avi::AviParser::new(RiffParser::new(reader)).unwrap();
+    let avi_reference = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    assert_eq!(
+        avi_parser
+            .riff_parser()
+            .riff_at(0)
+            .unwrap()
+            .header_position(),
+        0
+    );
+    let stream_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    for (chunk, reference) in avi_parser
+        .movi_chunks(stream_id)
+        .zip(avi_reference.movi_chunks(stream_id))
+    {
+        let (chunk, reference) = (chunk.unwrap(), reference.unwrap());
+        assert_eq!(chunk.position(), reference.position());
+        assert_eq!(
+            avi_parser.riff_parser().read_data_vec(chunk).unwrap(),
+            avi_reference
+                .riff_parser()
+                .read_data_vec(reference)
+                .unwrap()
+        );
+    }
+
+    let mut reader = OffsetReader::new(Cursor::new(embedded.as_slice()), 100).unwrap();
+    assert_eq!(
+        reader.seek(SeekFrom::End(0)).unwrap(),
+        TEST_AVI.len() as u64
+    );
+    assert_eq!(reader.seek(SeekFrom::Start(8)).unwrap(), 8);
+    let mut fourcc = [0u8; 4];
+    reader.read_exact(&mut fourcc).unwrap();
+    assert_eq!(&fourcc, b"AVI ");
+    assert_eq!(reader.seek(SeekFrom::Current(-12)).unwrap(), 0);
+    assert!(reader.seek(SeekFrom::Current(-1)).is_err());
+    assert_eq!(reader.into_inner().position(), 100);
+}
+
+#[test]
+fn test_buffered_reader() {
+    use riffparse::io::{BufferedReader, CountingReader};
+
+    let mut reader = BufferedReader::new(CountingReader::new(Cursor::new(TEST_AVI)), 64);
+    assert_eq!(reader.capacity(), 64);
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header).unwrap();
+    assert_eq!(&header[..4], b"RIFF");
+    // Seeking back and forward inside the buffer doesn't read again
+    reader.seek(SeekFrom::Start(8)).unwrap();
+    let mut fourcc = [0u8; 4];
+    reader.read_exact(&mut fourcc).unwrap();
+    assert_eq!(&fourcc, b"AVI ");
+    reader.seek(SeekFrom::Current(40)).unwrap();
+    reader.read_exact(&mut fourcc).unwrap();
+    assert_eq!(fourcc, TEST_AVI[52..56]);
+    assert_eq!(reader.stream_position().unwrap(), 56);
+    // Past the buffer it's refilled from the new position
+    reader.seek(SeekFrom::Start(100)).unwrap();
+    reader.read_exact(&mut fourcc).unwrap();
+    assert_eq!(fourcc, TEST_AVI[100..104]);
+    // Reads as large as the buffer bypass it
+    let mut large = [0u8; 64];
+    reader.seek(SeekFrom::Start(200)).unwrap();
+    reader.read_exact(&mut large).unwrap();
+    assert_eq!(large, TEST_AVI[200..264]);
+    assert_eq!(reader.stream_position().unwrap(), 264);
+    assert_eq!(reader.into_inner().bytes_read(), 64 * 3);
+}
+
+#[test]
+fn test_caching_reader() {
+    use riffparse::io::{CachingReader, CountingReader};
+
+    let mut reader = CachingReader::new(CountingReader::new(Cursor::new(TEST_AVI))).unwrap();
+    let mut buffer = [0u8; 12];
+    reader.read_exact(&mut buffer).unwrap();
+    assert_eq!(&buffer[..4], b"RIFF");
+    assert_eq!(reader.cache_size_bytes(), 12);
+    reader.seek(SeekFrom::Start(8)).unwrap();
+    let mut fourcc = [0u8; 4];
+    reader.read_exact(&mut fourcc).unwrap();
+    assert_eq!(&fourcc, b"AVI ");
+    // Served from the cache up to offset 12, the rest from the reader
+    reader.seek(SeekFrom::Start(4)).unwrap();
+    reader.read_exact(&mut buffer).unwrap();
+    assert_eq!(&buffer[8..], &TEST_AVI[12..16]);
+    assert_eq!(reader.cache_size_bytes(), 16);
+    assert_eq!(reader.stream_position().unwrap(), 16);
+    reader.clear_cache();
+    assert_eq!(reader.cache_size_bytes(), 0);
+    assert_eq!(reader.into_inner().bytes_read(), 16);
+
+    let reader = CachingReader::new(Cursor::new(TEST_AVI)).unwrap();
+    let avi_parser = avi::AviParser::new(RiffParser::new(reader)).unwrap();
+    let avi_reference = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let stream_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    for _ in 0..2 {
+        for (chunk, reference) in avi_parser
+            .movi_chunks(stream_id)
+            .zip(avi_reference.movi_chunks(stream_id))
+        {
+            assert_eq!(
+                avi_parser
+                    .riff_parser()
+                    .read_data_vec(chunk.unwrap())
+                    .unwrap(),
+                avi_reference
+                    .riff_parser()
+                    .read_data_vec(reference.unwrap())
+                    .unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_verify_size() {
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let riff = parser.riff().unwrap();
+    let Some(Ok(RiffType::List(hdrl))) = parser.chunks(riff).next() else {
+        panic!("hdrl not found");
+    };
+    let Some(Ok(RiffType::Chunk(avih))) = parser.chunks(hdrl).next() else {
+        panic!("avih not found");
+    };
+    avih.verify_size(56).unwrap();
+    assert_eq!(avih.total_size_on_disk(), 64);
+    assert_eq!(hdrl.total_size_on_disk(), 8 + 8892);
+    assert!(matches!(
+        avih.verify_size(64),
+        Err(RiffError::MalformedChunk { position: 32, .. })
+    ));
+}
+
+#[test]
+fn test_compare_data() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let parser = avi_parser.riff_parser();
+    let stream_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    let chunk = avi_parser.movi_chunks(stream_id).next().unwrap().unwrap();
+    let mut data = parser.read_data_vec(chunk).unwrap();
+
+    assert!(parser.compare_data(chunk, &data).unwrap());
+    assert!(!parser.compare_data(chunk, &data[1..]).unwrap());
+    *data.last_mut().unwrap() ^= 1;
+    assert!(!parser.compare_data(chunk, &data).unwrap());
+}
+
+#[test]
+fn test_chunk_reader() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let parser = avi_parser.riff_parser();
+    let stream_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    let mut chunks = avi_parser.movi_chunks(stream_id);
+    let chunk = chunks.next().unwrap().unwrap();
+    let data = parser.read_data_vec(chunk).unwrap();
+
+    let mut reader = chunk.into_reader(parser);
+    let mut streamed = Vec::new();
+    let mut buffer = [0u8; 100];
+    loop {
+        let read = reader.read(&mut buffer).unwrap();
+        if read == 0 {
+            break;
+        }
+        streamed.extend_from_slice(&buffer[..read]);
+        // Move the shared reader between reads
+        chunks.next();
+    }
+    assert_eq!(streamed, data);
+    assert_eq!(reader.remaining(), 0);
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn test_digest() {
+    use riffparse::digest::DigestList;
+    use sha1::{Digest, Sha1};
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let parser = avi_parser.riff_parser();
+    let mut digest_list = DigestList::<Sha1, _>::new(parser, avi_parser.movi);
+    let count = digest_list.by_ref().map(Result::unwrap).count();
+    assert_eq!(digest_list.digests().len(), count);
+
+    let (id, output) = &digest_list.digests()[0];
+    let stream_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    assert_eq!(*id, stream_id);
+    let chunk = avi_parser.movi_chunks(stream_id).next().unwrap().unwrap();
+    assert_eq!(*output, Sha1::digest(parser.read_data_vec(chunk).unwrap()));
+}
+
+#[test]
+fn test_chunk_filter() {
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let riff = parser.riff().unwrap();
+    let ids = |filter: ChunkFilter| {
+        parser
+            .chunks_filtered(riff, filter)
+            .map(|item| item.unwrap().id())
+            .collect::<Vec<_>>()
+    };
+    let junk = tag::JUNK;
+    let info = tag::INFO;
+
+    assert_eq!(
+        ids(ChunkFilter::new()),
+        [avi::tag::HDRL, info, junk, avi::tag::MOVI, avi::tag::IDX1]
+    );
+    assert_eq!(
+        ids(ChunkFilter::new().lists_only()),
+        [avi::tag::HDRL, info, avi::tag::MOVI]
+    );
+    assert_eq!(
+        ids(ChunkFilter::new().chunks_only()),
+        [junk, avi::tag::IDX1]
+    );
+    assert_eq!(
+        ids(ChunkFilter::new()
+            .include_id(junk)
+            .include_id(avi::tag::MOVI)),
+        [junk, avi::tag::MOVI]
+    );
+    assert_eq!(
+        ids(ChunkFilter::new().lists_only().exclude_id(info)),
+        [avi::tag::HDRL, avi::tag::MOVI]
+    );
+}
+
+#[test]
+fn test_avi_parser() {
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let avi_parser = avi::AviParser::new(parser).unwrap();
+
+    let avi::StreamInfo::Video(ref video_stream) = avi_parser.stream_info[0] else {
+        panic!("stream 0 not video");
+    };
+    let avi::StreamInfo::Audio(ref audio_stream) = avi_parser.stream_info[1] else {
+        panic!("stream 1 not audio");
+    };
+    let Some(stream) = avi_parser.find_best_stream::<avi::VideoStream>() else {
+        panic!("stream not found");
+    };
+    assert!(video_stream.strd.is_none() && video_stream.strn.is_none());
+    let Some(ref video_properties) = video_stream.video_properties else {
+        panic!("vprp not parsed");
+    };
+    assert_eq!(
+        video_properties.frame_aspect_ratio,
+        avi::FrameAspectRatio { x: 4, y: 3 }
+    );
+    assert_eq!(video_properties.frame_width_in_pixels, 32);
+    assert_eq!(video_properties.field_info.len(), 1);
+    assert_eq!(stream.stream_header.priority, 0);
+    assert_eq!(stream.stream_id, video_stream.stream_id);
+
+    assert_eq!(avi_parser.movi_chunks(video_stream.stream_id).count(), 20);
+    assert_eq!(avi_parser.movi_chunks(audio_stream.stream_id).count(), 15);
+
+    let Some(avi::StreamInfo::Audio(stream)) = avi_parser.find_stream_by_type(avi::tag::AUDS)
+    else {
+        panic!("audio stream not found");
+    };
+    assert_eq!(stream.stream_id, audio_stream.stream_id);
+    assert!(avi_parser.find_stream_by_type(avi::tag::TXTS).is_none());
+    assert_eq!(avi_parser.stream_count(), 2);
+    assert_eq!(avi_parser.streams().count(), 2);
+    assert!(matches!(
+        avi_parser.stream_at(1),
+        Some(avi::StreamInfo::Audio(_))
+    ));
+    assert!(avi_parser.stream_at(2).is_none());
+    let by_type = avi_parser.streams_by_type();
+    assert_eq!(by_type.len(), 2);
+    assert_eq!(by_type.get(avi::tag::VIDS).map(Vec::len), Some(1));
+    assert!(by_type.get(avi::tag::TXTS).is_none());
+
+    assert!(avi_parser.odml_header.is_none());
+    assert_eq!(avi_parser.true_total_frames(), 20);
+    assert_eq!(avi_parser.movi_size_bytes(), 56178);
+    assert_eq!(avi_parser.header_size_bytes(), 9970);
+}
+
+#[test]
+fn test_avi_bitrate() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    assert_eq!(avi_parser.average_bitrate_bps(), Some(533824));
+    let bitrate = avi_parser.bitrate().unwrap();
+    assert_eq!(bitrate.as_kbps(), 533.824);
+    assert_eq!(bitrate.as_mbps(), 0.533824);
+    assert_eq!(
+        avi_parser.stream_info[0].average_bitrate_bps(),
+        Some(368640)
+    );
+    assert_eq!(
+        avi_parser.stream_info[1].average_bitrate_bps(),
+        Some(256000)
+    );
+
+    // Crafted header values overflow u64 instead of panicking
+    let mut avi_parser = avi_parser;
+    if let avi::StreamInfo::Video(video) = &mut avi_parser.stream_info[0] {
+        video.bitmap_info.size_image = u32::MAX;
+        video.stream_header.rate = u32::MAX;
+        video.stream_header.scale = 1;
+    }
+    assert_eq!(avi_parser.stream_info[0].average_bitrate_bps(), None);
+
+    // mp3.avi has no video so no frame duration
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(MP3_AVI))).unwrap();
+    assert_eq!(avi_parser.average_bitrate_bps(), None);
+}
+
+#[test]
+fn test_avi_first_frame() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    assert!(avi_parser.idx1.is_some());
+    for stream in &avi_parser.stream_info {
+        let stream_id = match stream {
+            avi::StreamInfo::Video(v) => v.stream_id,
+            avi::StreamInfo::Audio(a) => a.stream_id,
+            avi::StreamInfo::Text(_) | avi::StreamInfo::Other(_) => unreachable!(),
+        };
+        let chunk = avi_parser.movi_chunks(stream_id).next().unwrap().unwrap();
+        let expected = avi_parser.riff_parser().read_data_vec(chunk).unwrap();
+        assert_eq!(avi_parser.first_frame(stream_id).unwrap(), expected);
+    }
+    let thumbnail = avi_parser.thumbnail().unwrap().unwrap();
+    assert_eq!(thumbnail.len(), 1267);
+    assert_eq!(&thumbnail[..2], [0xff, 0xd8]);
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(MP3_AVI))).unwrap();
+    assert!(avi_parser.thumbnail().unwrap().is_none());
+}
+
+#[test]
+fn test_avi_audio_sample_count() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    assert_eq!(avi_parser.audio_sample_count(), Some(15360));
+
+    // Extensible PCM counts every channel like plain PCM
+    let mut avi_parser = avi_parser;
+    let avi::StreamInfo::Audio(audio) = &mut avi_parser.stream_info[1] else {
+        panic!("expected audio stream");
+    };
+    audio.wave_format = avi::WaveFormat::Extensible(avi::WaveFormatExtensible {
+        format: audio.wave_format.format().clone(),
+        samples: 16,
+        channel_mask: 0x3,
+        sub_format: avi::Guid {
+            data1: 0x0001,
+            data2: 0x0000,
+            data3: 0x0010,
+            data4: [0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71],
+        },
+    });
+    assert_eq!(audio.total_samples(), 15360);
+
+    // Crafted header values saturate instead of overflowing
+    audio.stream_header.length = u32::MAX;
+    audio.stream_header.scale = u32::MAX;
+    audio.stream_header.rate = 1;
+    assert_eq!(audio.total_samples(), u64::MAX);
+
+    // 58 mp3 frames of 576 samples
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(MP3_AVI))).unwrap();
+    assert_eq!(avi_parser.audio_sample_count(), Some(58 * 576));
+}
+
+#[test]
+fn test_avi_try_from_chunk() {
+    let parser = RiffParser::from_bytes(TEST_AVI);
+    let riff = parser.riff().unwrap();
+    let Some(Ok(RiffType::List(hdrl))) = parser.chunks(riff).next() else {
+        panic!("expected hdrl");
+    };
+    let mut items = parser.chunks(hdrl);
+    let Some(Ok(RiffType::Chunk(avih))) = items.next() else {
+        panic!("expected avih");
+    };
+    let avi_header = avi::AviMainHeader::try_from((&parser, avih)).unwrap();
+    assert_eq!(avi_header.micro_sec_per_frame, 50000);
+    assert!(avi::AviStreamHeader::try_from((&parser, avih)).is_err());
+
+    let Some(Ok(RiffType::List(strl))) = items.next() else {
+        panic!("expected strl");
+    };
+    let strl: Vec<_> = parser.chunks(strl).map(Result::unwrap).collect();
+    let [RiffType::Chunk(strh), RiffType::Chunk(strf), ..] = strl[..] else {
+        panic!("expected strh and strf");
+    };
+    let stream_header = avi::AviStreamHeader::try_from((&parser, strh)).unwrap();
+    assert_eq!(stream_header.fcc_type, avi::tag::VIDS);
+    let bitmap_info = avi::BitmapInfo::try_from((&parser, strf)).unwrap();
+    assert_eq!(bitmap_info.width, 32);
+}
+
+#[test]
+fn test_codec_registry() {
+    use riffparse::fourcc::Fourcc;
+
+    let mut registry = codec::CodecRegistry::default();
+    let mjpeg = registry.describe_video(Fourcc::new(*b"MJPG")).unwrap();
+    assert_eq!(mjpeg.name, "MJPEG");
+    assert!(mjpeg.lossy);
+    assert_eq!(
+        registry.describe_video(Fourcc::new(*b"avc1")).unwrap().name,
+        "H.264"
+    );
+    assert!(!registry.describe_audio(0x0001).unwrap().lossy);
+    assert_eq!(registry.describe_audio(0x0055).unwrap().name, "MP3");
+    assert!(registry.describe_video(Fourcc::new(*b"ABCD")).is_none());
+    assert!(registry.describe_audio(0x1234).is_none());
+
+    let custom = codec::CodecDescriptor::new("Custom", "Custom codec", false);
+    registry.register_video(Fourcc::new(*b"ABCD"), custom);
+    registry.register_audio(0x0001, custom);
+    assert_eq!(
+        registry.describe_video(Fourcc::new(*b"ABCD")),
+        Some(&custom)
+    );
+    assert_eq!(registry.describe_audio(0x0001), Some(&custom));
+    assert!(
+        codec::CodecRegistry::empty()
+            .describe_audio(0x0001)
+            .is_none()
+    );
+}
+
+#[test]
+fn test_avi_video_timestamps() {
+    let mut avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let timestamps: Vec<_> = avi_parser.video_timestamps().collect();
+    assert_eq!(timestamps.len(), 20);
+    assert_eq!(timestamps[..3], [0, 50_000, 100_000]);
+    assert_eq!(timestamps[19], 950_000);
+
+    let avi::StreamInfo::Video(video) = &mut avi_parser.stream_info[0] else {
+        panic!("expected video stream");
+    };
+    video.stream_header.start = 2;
+    assert_eq!(avi_parser.video_timestamps().next(), Some(100_000));
+    assert_eq!(avi_parser.video_timestamps().count(), 20);
+}
+
+#[test]
+fn test_avi_audio_preroll() {
+    use core::time::Duration;
+
+    let mut avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    assert_eq!(avi_parser.audio_preroll_duration(), Some(Duration::ZERO));
+    avi_parser.avi_header.initial_frames = 15;
+    assert_eq!(
+        avi_parser.audio_preroll_duration(),
+        Some(Duration::from_millis(750))
+    );
+    let avi::StreamInfo::Audio(audio) = &mut avi_parser.stream_info[1] else {
+        panic!("expected audio stream");
+    };
+    assert_eq!(audio.preroll_samples(), 0);
+    audio.stream_header.initial_frames = 12000;
+    assert_eq!(audio.preroll_samples(), 12000);
+
+    let mut avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(MP3_AVI))).unwrap();
+    assert_eq!(avi_parser.audio_preroll_duration(), None);
+    let avi::StreamInfo::Audio(audio) = &mut avi_parser.stream_info[0] else {
+        panic!("expected audio stream");
+    };
+    // 2 mp3 frames of 576 samples
+    audio.stream_header.initial_frames = 2;
+    assert_eq!(audio.preroll_samples(), 2 * 576);
+}
+
+#[test]
+fn test_info() {
+    use riffparse::info::{self, InfoList};
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let Some(ref info) = avi_parser.info else {
+        panic!("INFO not parsed");
+    };
+    assert_eq!(info.get(info::tag::ISFT), Some("Lavf62.3.100"));
+    assert_eq!(info.digitization_date_raw(), None);
+
+    let info_list = |idit: &[u8]| {
+        let data = build_list(
+            b"RIFF",
+            b"AVI ",
+            &[build_list(b"LIST", b"INFO", &[build_chunk(b"IDIT", idit)])],
+        );
+        let parser = RiffParser::new(Cursor::new(data));
+        let riff = parser.riff().unwrap();
+        let Some(Ok(RiffType::List(list))) = parser.chunks(riff).next() else {
+            panic!("INFO not found");
+        };
+        InfoList::read(&parser, list).unwrap()
+    };
+    let asctime = info_list(b"Wed Jan 02 02:03:55 1990\r\n\0");
+    assert_eq!(
+        asctime.digitization_date_raw(),
+        Some("Wed Jan 02 02:03:55 1990")
+    );
+
+    #[cfg(feature = "chrono")]
+    {
+        use chrono::NaiveDate;
+        let date = |y, m, d, h, min, s| {
+            NaiveDate::from_ymd_opt(y, m, d)
+                .unwrap()
+                .and_hms_opt(h, min, s)
+                .unwrap()
+        };
+        assert_eq!(
+            asctime.digitization_date(),
+            Some(date(1990, 1, 2, 2, 3, 55))
+        );
+        // 1990-01-02 was a Tuesday, the weekday is ignored either way
+        assert_eq!(
+            info_list(b"Tue Jan 02 02:03:55 1990").digitization_date(),
+            Some(date(1990, 1, 2, 2, 3, 55))
+        );
+        for idit in [
+            &b"2005:08:17 11:42:43\0"[..],
+            b"2005-08-17 11:42:43",
+            b"2005-08-17T11:42:43\n",
+        ] {
+            assert_eq!(
+                info_list(idit).digitization_date(),
+                Some(date(2005, 8, 17, 11, 42, 43))
+            );
+        }
+        assert_eq!(info_list(b"yesterday").digitization_date(), None);
+    }
+}
+
+#[test]
+fn test_avi_repair() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let mut output = Cursor::new(Vec::new());
+    let report = avi_parser.repair(&mut output).unwrap();
+    assert_eq!(
+        report,
+        avi::RepairReport {
+            total_frames: None,
+            index_missing: false,
+            index_entries: 35,
+            list_sizes_corrected: 0,
+            padded_chunks: 14,
+        }
+    );
+    assert_eq!(output.into_inner(), TEST_AVI);
+
+    // Drop idx1 and break total_frames
+    let mut broken = TEST_AVI[..66160].to_vec();
+    broken[4..8].copy_from_slice(&(66160u32 - 8).to_le_bytes());
+    // total_frames is 16 bytes into the avih data at 32
+    broken[48..52].copy_from_slice(&5u32.to_le_bytes());
+    let avi_parser = avi::AviParser::new(RiffParser::from_vec(broken)).unwrap();
+    assert_eq!(avi_parser.avi_header.total_frames, 5);
+    assert!(avi_parser.idx1.is_none());
+    let mut output = Cursor::new(Vec::new());
+    let report = avi_parser.repair(&mut output).unwrap();
+    assert_eq!(report.total_frames, Some((5, 20)));
+    assert!(report.index_missing);
+    assert_eq!(output.into_inner(), TEST_AVI);
+
+    // RIFX stays big endian
+    fn to_rifx(data: &[u8], rifx: &mut Vec<u8>) {
+        let mut position = 0;
+        while position + 8 <= data.len() {
+            let id = &data[position..position + 4];
+            let size = u32::from_le_bytes(data[position + 4..position + 8].try_into().unwrap());
+            rifx.extend_from_slice(if id == b"RIFF" { b"RIFX" } else { id });
+            rifx.extend_from_slice(&size.to_be_bytes());
+            let content = &data[position + 8..position + 8 + size as usize];
+            if id == b"RIFF" || id == b"LIST" {
+                rifx.extend_from_slice(&content[..4]);
+                to_rifx(&content[4..], rifx);
+            } else {
+                rifx.extend_from_slice(content);
+            }
+            position += 8 + size as usize;
+            if size % 2 == 1 {
+                rifx.push(data[position]);
+                position += 1;
+            }
+        }
+    }
+    let mut rifx = Vec::new();
+    to_rifx(TEST_AVI, &mut rifx);
+    let avi_parser = avi::AviParser::new(RiffParser::from_vec(rifx.clone())).unwrap();
+    let mut output = Cursor::new(Vec::new());
+    avi_parser.repair(&mut output).unwrap();
+    assert_eq!(output.into_inner(), rifx);
+}
+
+#[test]
+fn test_avi_export_frames() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let stream_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    let mut sizes = Vec::new();
+    avi_parser
+        .export_frames(stream_id, |index, data| {
+            assert_eq!(index, sizes.len());
+            sizes.push(data.len());
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(sizes.len(), 20);
+    assert_eq!(sizes[0], 1267);
+    assert!(
+        avi_parser
+            .export_frames(avi::tag::stream(1, avi::tag::DATA_AUDIO), |_, _| Ok(()))
+            .is_err()
+    );
+
+    #[cfg(feature = "std")]
+    {
+        let dir = std::env::temp_dir().join(format!("riffparse-frames-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        avi_parser
+            .export_frames_to_dir(stream_id, &dir, "jpg")
+            .unwrap();
+        let first = std::fs::read(dir.join("frame_000000.jpg")).unwrap();
+        assert_eq!(first.len(), 1267);
+        assert!(dir.join("frame_000019.jpg").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn test_avi_export_wav() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let stream_id = avi::tag::stream(1, avi::tag::DATA_AUDIO);
+    let mut wav = Cursor::new(Vec::new());
+    avi_parser.export_wav(stream_id, &mut wav).unwrap();
+    assert!(
+        avi_parser
+            .export_wav(
+                avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED),
+                &mut wav
+            )
+            .is_err()
+    );
+
+    let wav = wav.into_inner();
+    let parser = RiffParser::new(Cursor::new(&wav));
+    let riff = parser.riff().unwrap();
+    assert_eq!(riff.id(), tag::WAVE);
+    assert_eq!(riff.data_size() as usize + 4, wav.len() - 8);
+    let chunks: Vec<_> = parser.chunks(riff).map(Result::unwrap).collect();
+    let [RiffType::Chunk(fmt), RiffType::Chunk(data)] = chunks[..] else {
+        panic!("expected fmt and data chunks");
+    };
+    assert_eq!(fmt.id(), tag::FMT);
+    let avi::WaveFormat::Pcm(format) = parser.read_data_struct::<avi::WaveFormat>(fmt).unwrap()
+    else {
+        panic!("expected PCM");
+    };
+    assert_eq!(format.samples_per_sec, 16000);
+    assert_eq!(data.id(), tag::DATA);
+    assert_eq!(data.data_size(), 15 * 2048);
+    let first = avi_parser.first_frame(stream_id).unwrap();
+    assert_eq!(parser.read_data_vec(data).unwrap()[..2048], first[..]);
+}
+
+#[cfg(all(feature = "log", feature = "std", debug_assertions))]
+#[test]
+fn test_interleaved_iterator_warning() {
+    use std::cell::RefCell;
+
+    std::thread_local! {
+        static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Captures warnings per thread so other tests logging concurrently don't interfere
+    struct CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.with_borrow_mut(|warnings| warnings.push(format!("{}", record.args())));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    log::set_logger(&CaptureLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+    let take_warnings = || WARNINGS.with_borrow_mut(core::mem::take);
+
+    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+    parser.depth_first_walk(|_| WalkControl::Continue).unwrap();
+    let riff = parser.riff_at(0).unwrap();
+
+    // Reading data after each advance, and nested iterators, don't warn
+    for item in parser.chunks(riff) {
+        match item.unwrap() {
+            RiffType::Chunk(chunk) => {
+                parser.read_data_vec(chunk).unwrap();
+            }
+            RiffType::List(list) => {
+                for item in parser.chunks(list) {
+                    item.unwrap();
+                }
+            }
+        }
+    }
+    assert_eq!(take_warnings(), Vec::<String>::new());
+
+    // Two live iterators advancing in turn share the reader
+    let mut first = parser.chunks(riff);
+    let mut second = parser.chunks(riff);
+    first.next().unwrap().unwrap();
+    second.next().unwrap().unwrap();
+    first.next().unwrap().unwrap();
+    let warnings = take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0],
+        "interleaved iterators in AVI  list at 0xc: another iterator advanced the reader"
+    );
+}
+
+#[test]
+fn test_skip() {
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+    use riffparse::binrw::io;
+
+    /// Shares the reader position with the test after the parser takes ownership
+    struct Tracked {
+        inner: Cursor<Vec<u8>>,
+        position: Rc<Cell<u64>>,
+    }
+
+    impl Read for Tracked {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.inner.read(buf)?;
+            self.position.set(self.inner.position());
+            Ok(read)
+        }
+    }
+
+    impl Seek for Tracked {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            let position = self.inner.seek(pos)?;
+            self.position.set(position);
+            Ok(position)
+        }
+    }
+
+    let data = build_list(
+        b"RIFF",
+        b"TEST",
+        &[
+            build_chunk(b"odd ", b"abc"),
+            build_list(b"LIST", b"sub ", &[build_chunk(b"abcd", b"a")]),
+            build_chunk(b"last", b"ab"),
+        ],
+    );
+    let position = Rc::new(Cell::new(0));
+    let parser = RiffParser::new(Tracked {
+        inner: Cursor::new(data),
+        position: position.clone(),
+    });
+    let riff = parser.riff().unwrap();
+    let items: Vec<RiffType> = parser.chunks(riff).map(Result::unwrap).collect();
+
+    // Odd sized chunk skips its pad byte
+    let RiffType::Chunk(odd) = items[0] else {
+        panic!("expected chunk");
+    };
+    parser.skip(odd).unwrap();
+    assert_eq!(position.get(), odd.full_byte_range().end);
+    assert_eq!(position.get(), 24);
+
+    // List skips all of its children
+    let RiffType::List(list) = items[1] else {
+        panic!("expected list");
+    };
+    parser.skip(list).unwrap();
+    assert_eq!(position.get(), list.full_byte_range().end);
+    assert_eq!(position.get(), 46);
+}
+
+#[test]
+fn test_wav_writer() {
+    use riffparse::wav::WavWriter;
+
+    let format = avi::WaveFormatEx {
+        channels: 1,
+        samples_per_sec: 8000,
+        av_bytes_per_sec: 8000,
+        block_align: 1,
+        bits_per_sample: 8,
+        size: 0,
+    };
+    let mut wav = WavWriter::new(Cursor::new(Vec::new()), &format).unwrap();
+    wav.write_samples(&[1, 2, 3]).unwrap();
+    wav.write_samples(&[4, 5, 6, 7]).unwrap();
+    assert_eq!(wav.data_size(), 7);
+    let wav = wav.finalize().unwrap().into_inner();
+
+    let parser = RiffParser::from_vec(wav.clone());
+    let riff = parser.riff().unwrap();
+    assert_eq!(riff.id(), tag::WAVE);
+    assert_eq!(riff.full_byte_range().end, wav.len() as u64);
+    let fmt = parser.find_chunk(riff, tag::FMT).unwrap().unwrap();
+    let avi::WaveFormat::Pcm(read) = parser.read_data_struct::<avi::WaveFormat>(fmt).unwrap()
+    else {
+        panic!("expected PCM");
+    };
+    assert_eq!(read.samples_per_sec, 8000);
+    let data = parser.find_chunk(riff, tag::DATA).unwrap().unwrap();
+    assert_eq!(parser.read_data_vec(data).unwrap(), [1, 2, 3, 4, 5, 6, 7]);
+    assert!(wav.len().is_multiple_of(2));
+}
+
+#[test]
+fn test_wav_writer_size_limit() {
+    use riffparse::{binrw::io, wav::WavWriter};
+
+    /// Discards writes but tracks the position so 4GB can be written quickly
+    #[derive(Default)]
+    struct Sink {
+        position: u64,
+        len: u64,
+    }
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.position += buf.len() as u64;
+            self.len = self.len.max(self.position);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for Sink {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.position = match pos {
+                SeekFrom::Start(offset) => offset,
+                SeekFrom::End(offset) => self.len.wrapping_add_signed(offset),
+                SeekFrom::Current(offset) => self.position.wrapping_add_signed(offset),
+            };
+            Ok(self.position)
+        }
+    }
+
+    let format = avi::WaveFormatEx {
+        channels: 1,
+        samples_per_sec: 8000,
+        av_bytes_per_sec: 8000,
+        block_align: 1,
+        bits_per_sample: 8,
+        size: 0,
+    };
+    let mut wav = WavWriter::new(Sink::default(), &format).unwrap();
+    let block = vec![0; 1 << 20];
+    // 4GB of data doesn't leave room for the headers in the RIFF size
+    while wav.write_samples(&block).is_ok() {}
+    assert!(matches!(
+        wav.write_samples(&block),
+        Err(RiffError::MalformedChunk { .. })
+    ));
+    // Fill up to the limit, the RIFF size counts 38 header bytes and must still fit
+    let remaining = (u32::MAX - 38 - wav.data_size()) as usize & !1;
+    wav.write_samples(&block[..remaining]).unwrap();
+    assert!(wav.write_samples(&[0]).is_err());
+    let sink = wav.finalize().unwrap();
+    assert_eq!(sink.len, u32::MAX as u64 + 7);
+}
+
+#[test]
+fn test_avi_transcode_audio() {
+    use avi::{AudioEncoder, EncoderError, WaveFormat};
+
+    /// 16 bit signed to 8 bit unsigned PCM
+    struct To8Bit;
+
+    impl AudioEncoder for To8Bit {
+        fn output_format(&self, format: &WaveFormat) -> WaveFormat {
+            let mut format = format.format().clone();
+            format.bits_per_sample = 8;
+            format.block_align = format.channels;
+            format.av_bytes_per_sec = format.samples_per_sec * format.channels as u32;
+            WaveFormat::Pcm(format)
+        }
+
+        fn encode(&mut self, _: &WaveFormat, chunk_data: &[u8]) -> Result<Vec<u8>, EncoderError> {
+            Ok(chunk_data
+                .chunks_exact(2)
+                .map(|sample| sample[1] ^ 0x80)
+                .collect())
+        }
+    }
+
+    struct Failing;
+
+    impl AudioEncoder for Failing {
+        fn encode(&mut self, _: &WaveFormat, _: &[u8]) -> Result<Vec<u8>, EncoderError> {
+            Err(EncoderError::new("unsupported"))
         }
     }
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let stream_id = avi::tag::stream(1, avi::tag::DATA_AUDIO);
+    let mut wav = Cursor::new(Vec::new());
+    avi_parser
+        .transcode_audio(stream_id, To8Bit, &mut wav)
+        .unwrap();
+
+    let parser = RiffParser::from_vec(wav.into_inner());
+    let riff = parser.riff().unwrap();
+    let fmt = parser.find_chunk(riff, tag::FMT).unwrap().unwrap();
+    let WaveFormat::Pcm(format) = parser.read_data_struct::<WaveFormat>(fmt).unwrap() else {
+        panic!("expected PCM");
+    };
+    assert_eq!(format.bits_per_sample, 8);
+    assert_eq!(format.av_bytes_per_sec, 16000);
+    let data = parser.find_chunk(riff, tag::DATA).unwrap().unwrap();
+    assert_eq!(data.data_size(), 15 * 1024);
+    let audio = avi_parser.find_best_stream::<avi::AudioStream>().unwrap();
+    let first = avi_parser.first_frame(stream_id).unwrap();
+    assert_eq!(
+        parser.read_data_vec(data).unwrap()[..8],
+        To8Bit.encode(&audio.wave_format, &first[..16]).unwrap()[..]
+    );
+
+    let result = avi_parser.transcode_audio(stream_id, Failing, &mut Cursor::new(Vec::new()));
+    assert!(matches!(result, Err(RiffError::Custom(_))));
+    assert!(format!("{}", result.unwrap_err()).contains("unsupported"));
 }
 
-fn dump_avi<R: Read + Seek + Debug, W: Write>(avi: R, output: &mut W) {
-    let parser = RiffParser::new(avi);
-    process_list(&parser, parser.riff().unwrap(), output, 0);
+#[test]
+fn test_avi_rebuild_index() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let index = avi_parser.read_index().unwrap().unwrap();
+    assert_eq!(index.entries.len(), 35);
+    let rebuilt = avi_parser.rebuild_index().unwrap();
+    assert_eq!(rebuilt, index);
+
+    let stream_id = avi::tag::stream(1, avi::tag::DATA_AUDIO);
+    assert_eq!(rebuilt.stream_entries(stream_id).count(), 15);
+    let idx1 = avi_parser.idx1.unwrap();
+    assert_eq!(
+        rebuilt.to_bytes(),
+        avi_parser.riff_parser().read_data_vec(idx1).unwrap()
+    );
 }
 
 #[test]
-fn test_test_avi() {
-    let mut output = Vec::new();
-    dump_avi(Cursor::new(TEST_AVI), &mut output);
-    assert_eq!(TEST_AVI_SNAPSHOT, String::from_utf8(output).unwrap());
+fn test_avi_interleaving() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let report = avi_parser.check_interleaving(4096).unwrap();
+    assert_eq!(report.chunk_counts, vec![20, 15]);
+    assert_eq!(report.max_video_gap_bytes, 2056);
+    assert_eq!(report.max_audio_gap_bytes, 2536);
+    assert!(report.well_interleaved);
+    assert!(
+        !avi_parser
+            .check_interleaving(2048)
+            .unwrap()
+            .well_interleaved
+    );
 }
 
 #[test]
-fn test_mp3_avi() {
-    let mut output = Vec::new();
-    dump_avi(Cursor::new(MP3_AVI), &mut output);
-    assert_eq!(MP3_AVI_SNAPSHOT, String::from_utf8(output).unwrap());
+fn test_wave_format_ex() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let Some(audio) = avi_parser.find_best_stream::<avi::AudioStream>() else {
+        panic!("audio stream not found");
+    };
+    let format = audio.wave_format.format();
+    assert_eq!(format.sample_duration_ns(), 62500);
+    assert_eq!(format.frame_size_bytes(), 2);
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(MP3_AVI))).unwrap();
+    let avi::StreamInfo::Audio(ref audio) = avi_parser.stream_info[0] else {
+        panic!("stream 0 not audio");
+    };
+    assert_eq!(audio.wave_format.format().frame_size_bytes(), 0);
 }
 
 #[test]
-fn test_cross_reader() {
-    let parser1 = RiffParser::new(Cursor::new(TEST_AVI));
-    let riff = parser1.riff().unwrap();
-    let mut chunks = parser1.chunks(riff);
-    chunks.next().unwrap().unwrap();
-    chunks.next().unwrap().unwrap();
-    let chunk = chunks.next().unwrap().unwrap();
-    let parser2 = RiffParser::new(Cursor::new(TEST_AVI));
-    match chunk {
-        RiffType::List(chunk) => {
-            assert_eq!(
-                parser1.read_data_vec(chunk).unwrap(),
-                parser2.read_data_vec(chunk).unwrap()
-            );
-        }
-        RiffType::Chunk(chunk) => {
-            assert_eq!(
-                parser1.read_data_vec(chunk).unwrap(),
-                parser2.read_data_vec(chunk).unwrap()
-            );
+fn test_avi_validate() {
+    for data in [TEST_AVI, MP3_AVI] {
+        let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+        assert_eq!(avi_parser.validate().unwrap(), Vec::new());
+    }
+
+    let mut data = TEST_AVI.to_vec();
+    data.truncate(data.len() - 4096);
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+    assert!(
+        avi_parser
+            .validate()
+            .unwrap()
+            .contains(&avi::ValidationWarning::MoviOutOfBounds {
+                end: 66160,
+                file_size: 62632,
+            })
+    );
+}
+
+#[test]
+fn test_avi_detect_corruption() {
+    for data in [TEST_AVI, MP3_AVI] {
+        let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+        assert_eq!(avi_parser.detect_corruption(), Vec::new());
+    }
+
+    let mut broken = TEST_AVI.to_vec();
+    // streams and total_frames of the avih data at 32
+    broken[56..60].copy_from_slice(&1u32.to_le_bytes());
+    broken[48..52].copy_from_slice(&0u32.to_le_bytes());
+    // idx1 at 66160 grows past the end of the RIFF list
+    broken[66164..66168].copy_from_slice(&600u32.to_le_bytes());
+    let mut avi_parser = avi::AviParser::new(RiffParser::from_vec(broken.clone())).unwrap();
+    let warnings = avi_parser.detect_corruption();
+    assert_eq!(
+        warnings
+            .iter()
+            .map(avi::CorruptionWarning::position)
+            .collect::<Vec<_>>(),
+        [66160, 12, 24]
+    );
+    assert!(matches!(
+        warnings[0],
+        avi::CorruptionWarning::ChunkPastListEnd { .. }
+    ));
+    assert_eq!(
+        format!("{}", warnings[1]),
+        "0xc: avih declares 1 streams but hdrl has 2 strl lists"
+    );
+    assert!(matches!(
+        warnings[2],
+        avi::CorruptionWarning::ZeroTotalFrames { .. }
+    ));
+
+    // A RIFF starting after other data is checked where it is
+    let mut embedded = vec![0; 100];
+    embedded.extend_from_slice(&broken);
+    let mut reader = Cursor::new(embedded);
+    reader.set_position(100);
+    let embedded_parser = avi::AviParser::new(RiffParser::new(reader)).unwrap();
+    assert_eq!(
+        embedded_parser
+            .detect_corruption()
+            .iter()
+            .map(avi::CorruptionWarning::position)
+            .collect::<Vec<_>>(),
+        [66260, 112, 124]
+    );
+
+    let stream = avi_parser.stream_info[0].clone();
+    avi_parser.stream_info.push(stream);
+    assert!(
+        avi_parser
+            .detect_corruption()
+            .iter()
+            .any(|warning| matches!(warning, avi::CorruptionWarning::DuplicateStreamId { .. }))
+    );
+}
+
+#[test]
+fn test_avi_video_resolution() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    assert_eq!(avi_parser.video_resolution(), Some((32, 24)));
+    assert_eq!(avi_parser.video_resolution_all(), [(32, 24)]);
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(MP3_AVI))).unwrap();
+    assert_eq!(avi_parser.video_resolution(), None);
+    assert!(avi_parser.video_resolution_all().is_empty());
+}
+
+#[test]
+fn test_avi_display_aspect_ratio() {
+    let mut avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let ratio = avi_parser.display_aspect_ratio().unwrap();
+    assert_eq!(
+        ratio,
+        avi::AspectRatio {
+            width: 4,
+            height: 3
         }
+    );
+    assert_eq!(format!("{ratio}"), "4:3");
+    assert!((ratio.as_f64() - 4.0 / 3.0).abs() < f64::EPSILON);
+
+    // Anamorphic, vprp takes precedence over the pixel dimensions
+    let avi::StreamInfo::Video(video) = &mut avi_parser.stream_info[0] else {
+        panic!("stream 0 is video");
+    };
+    video.bitmap_info.width = 720;
+    video.bitmap_info.height = 480;
+    assert_eq!(
+        avi_parser.display_aspect_ratio(),
+        avi::AspectRatio::new(4, 3)
+    );
+
+    let avi::StreamInfo::Video(video) = &mut avi_parser.stream_info[0] else {
+        panic!("stream 0 is video");
+    };
+    video.video_properties = None;
+    assert_eq!(
+        avi_parser.display_aspect_ratio(),
+        avi::AspectRatio::new(3, 2)
+    );
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(MP3_AVI))).unwrap();
+    assert_eq!(avi_parser.display_aspect_ratio(), None);
+}
+
+#[test]
+fn test_avi_stream_selection_policy() {
+    use avi::StreamSelectionPolicy;
+    use riffparse::fourcc::Fourcc;
+
+    let mut avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let avi::StreamInfo::Video(mut small) = avi_parser.stream_info[0].clone() else {
+        panic!("stream 0 is video");
+    };
+    small.stream_id = avi::tag::stream(2, avi::tag::DATA_VIDEO_COMPRESSED);
+    small.bitmap_info.width = 16;
+    small.bitmap_info.height = 12;
+    small.bitmap_info.compression = u32::from(Fourcc::new(*b"H264"));
+    small.stream_header.fcc_handler = Fourcc::new(*b"H264");
+    avi_parser.stream_info.push(avi::StreamInfo::Video(small));
+
+    fn best(avi_parser: &avi::AviParser<Cursor<&[u8]>>, policy: StreamSelectionPolicy) -> i32 {
+        avi_parser
+            .find_best_stream_with_policy::<avi::VideoStream>(policy)
+            .unwrap()
+            .bitmap_info
+            .width
     }
+    // Equal priorities, so the last stream wins
+    assert_eq!(
+        best(&avi_parser, StreamSelectionPolicy::HighestPriority),
+        16
+    );
+    assert_eq!(best(&avi_parser, StreamSelectionPolicy::BestQuality), 32);
+    assert_eq!(
+        best(
+            &avi_parser,
+            StreamSelectionPolicy::PreferCodec(Fourcc::new(*b"MJPG"))
+        ),
+        32
+    );
+    assert_eq!(
+        best(
+            &avi_parser,
+            StreamSelectionPolicy::PreferCodec(Fourcc::new(*b"H264"))
+        ),
+        16
+    );
+
+    let avi::StreamInfo::Video(video) = &mut avi_parser.stream_info[0] else {
+        panic!("stream 0 is video");
+    };
+    video.stream_header.priority = 1;
+    assert_eq!(
+        best(&avi_parser, StreamSelectionPolicy::HighestPriority),
+        32
+    );
+    assert_eq!(
+        best(
+            &avi_parser,
+            StreamSelectionPolicy::PreferCodec(Fourcc::new(*b"H264"))
+        ),
+        32
+    );
 }
 
 #[test]
-fn test_avi_parser() {
-    let parser = RiffParser::new(Cursor::new(TEST_AVI));
+fn test_avi_buffered() {
+    let parser = RiffParser::new_buffered(Cursor::new(TEST_AVI), 64);
     let avi_parser = avi::AviParser::new(parser).unwrap();
+    assert_eq!(
+        avi_parser.stream_info[0].stream_header().fcc_type,
+        avi::tag::VIDS
+    );
+    assert_eq!(
+        avi_parser
+            .movi_chunks(avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED))
+            .count(),
+        20
+    );
+}
+
+#[test]
+fn test_wave_format_display() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let audio = avi_parser.find_best_stream::<avi::AudioStream>().unwrap();
+    assert_eq!(
+        format!("{}", audio.wave_format),
+        "PCM 16000 Hz, 1 ch, 16-bit"
+    );
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(MP3_AVI))).unwrap();
+    let mut audio = avi_parser
+        .find_best_stream::<avi::AudioStream>()
+        .unwrap()
+        .clone();
+    assert_eq!(format!("{}", audio.wave_format), "MP3 16000 Hz, 1 ch");
+    let avi::WaveFormat::Mp3(mp3) = &mut audio.wave_format else {
+        panic!("mp3.avi is MP3");
+    };
+    mp3.format.av_bytes_per_sec = 16000;
+    assert_eq!(format!("{}", audio.wave_format), "MP3 128 kbps");
+
+    let mut extensible = avi::WaveFormatExtensible {
+        format: avi::WaveFormatEx {
+            channels: 2,
+            samples_per_sec: 48000,
+            av_bytes_per_sec: 384000,
+            block_align: 8,
+            bits_per_sample: 32,
+            size: 22,
+        },
+        samples: 32,
+        channel_mask: 3,
+        sub_format: avi::Guid {
+            data1: 3,
+            data2: 0,
+            data3: 0x10,
+            data4: [0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71],
+        },
+    };
+    assert_eq!(
+        format!("{}", avi::WaveFormat::Extensible(extensible.clone())),
+        "IEEE_FLOAT 48000 Hz, 2 ch, 32-bit"
+    );
+    extensible.sub_format.data1 = 0xfffe;
+    assert_eq!(
+        format!("{}", avi::WaveFormat::Extensible(extensible)),
+        "Extensible 0000FFFE-0000-0010-8000-00AA00389B71 48000 Hz, 2 ch, 32-bit"
+    );
+}
+
+#[test]
+fn test_guid() {
+    let mut guid = avi::Guid {
+        data1: 3,
+        data2: 0,
+        data3: 0x10,
+        data4: [0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71],
+    };
+    assert_eq!(
+        guid.to_uuid_string(),
+        "00000003-0000-0010-8000-00AA00389B71"
+    );
+    assert_eq!(guid.known_format(), Some("IEEE_FLOAT"));
+    guid.data1 = 0x0092;
+    assert_eq!(guid.known_format(), Some("DOLBY_AC3_SPDIF"));
+    guid.data1 = 0xfffe;
+    assert_eq!(guid.known_format(), None);
+
+    // KSDATAFORMAT_SUBTYPE_ATRAC3P does not use the common suffix
+    let guid = avi::Guid {
+        data1: 0xe923aabf,
+        data2: 0xcb58,
+        data3: 0x4471,
+        data4: [0xa1, 0x19, 0xff, 0xfa, 0x01, 0xe4, 0xce, 0x62],
+    };
+    assert_eq!(
+        guid.to_uuid_string(),
+        "E923AABF-CB58-4471-A119-FFFA01E4CE62"
+    );
+    assert_eq!(guid.known_format(), None);
+}
+
+#[test]
+fn test_avi_subtitle_streams() {
+    let mut avih = [0u8; 56];
+    avih[24..28].copy_from_slice(&2u32.to_le_bytes());
+    let mut vids = [0u8; 56];
+    vids[0..4].copy_from_slice(b"vids");
+    let mut bitmap_info = [0u8; 40];
+    bitmap_info[0..4].copy_from_slice(&40u32.to_le_bytes());
+    let mut txts = [0u8; 56];
+    txts[0..4].copy_from_slice(b"txts");
+    let subtitle = b"1\n00:00:00,000 --> 00:00:01,000\nHello\n";
+
+    let data = build_list(
+        b"RIFF",
+        b"AVI ",
+        &[
+            build_list(
+                b"LIST",
+                b"hdrl",
+                &[
+                    build_chunk(b"avih", &avih),
+                    build_list(
+                        b"LIST",
+                        b"strl",
+                        &[
+                            build_chunk(b"strh", &vids),
+                            build_chunk(b"strf", &bitmap_info),
+                        ],
+                    ),
+                    build_list(
+                        b"LIST",
+                        b"strl",
+                        &[
+                            build_chunk(b"strh", &txts),
+                            build_chunk(b"strf", &[]),
+                            build_chunk(b"strn", b"English\0"),
+                        ],
+                    ),
+                ],
+            ),
+            build_list(
+                b"LIST",
+                b"movi",
+                &[
+                    build_chunk(b"00dc", b"frame"),
+                    build_chunk(b"01tx", subtitle),
+                ],
+            ),
+        ],
+    );
+    let avi_parser = avi::AviParser::new(RiffParser::from_vec(data)).unwrap();
+    let subtitles: Vec<_> = avi_parser.subtitle_streams().collect();
+    assert_eq!(subtitles.len(), 1);
+    assert_eq!(
+        subtitles[0].stream_id,
+        avi::tag::stream(1, avi::tag::DATA_TEXT)
+    );
+    assert_eq!(subtitles[0].stream_header.fcc_type, avi::tag::TXTS);
+    assert_eq!(subtitles[0].strn.as_deref(), Some("English"));
+
+    let chunk = avi_parser
+        .movi_chunks(subtitles[0].stream_id)
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        avi_parser.riff_parser().read_data_vec(chunk).unwrap(),
+        subtitle
+    );
+}
+
+#[test]
+fn test_avi_palette_changes() {
+    let mut avih = [0u8; 56];
+    avih[24..28].copy_from_slice(&1u32.to_le_bytes());
+    let mut strh = [0u8; 56];
+    strh[0..4].copy_from_slice(b"vids");
+    strh[8..12].copy_from_slice(&avi::AviStreamHeader::FLAG_VIDEO_PALETTE_CHANGES.to_le_bytes());
+    let mut strf = [0u8; 40];
+    strf[0..4].copy_from_slice(&40u32.to_le_bytes());
+    strf[14..16].copy_from_slice(&8u16.to_le_bytes());
+
+    let mut all = vec![0u8; 4];
+    all.extend((0..=255u8).flat_map(|i| [i, i, i, 0]));
+    let data = build_list(
+        b"RIFF",
+        b"AVI ",
+        &[
+            build_list(
+                b"LIST",
+                b"hdrl",
+                &[
+                    build_chunk(b"avih", &avih),
+                    build_list(
+                        b"LIST",
+                        b"strl",
+                        &[build_chunk(b"strh", &strh), build_chunk(b"strf", &strf)],
+                    ),
+                ],
+            ),
+            build_list(
+                b"LIST",
+                b"movi",
+                &[
+                    build_chunk(b"00db", b"frame"),
+                    build_chunk(b"00pc", &[16, 2, 0, 0, 1, 2, 3, 0, 4, 5, 6, 0]),
+                    build_chunk(b"00db", b"frame"),
+                    build_chunk(b"00pc", &all),
+                ],
+            ),
+        ],
+    );
+    let avi_parser = avi::AviParser::new(RiffParser::from_vec(data)).unwrap();
+    let changes: Vec<_> = avi_parser.palette_changes(0).map(Result::unwrap).collect();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].first_entry, 16);
+    assert_eq!(
+        changes[0].entries,
+        [
+            avi::PaletteEntry {
+                blue: 1,
+                green: 2,
+                red: 3,
+                reserved: 0
+            },
+            avi::PaletteEntry {
+                blue: 4,
+                green: 5,
+                red: 6,
+                reserved: 0
+            },
+        ]
+    );
+    assert_eq!(changes[1].num_entries, 0);
+    assert_eq!(changes[1].entries.len(), 256);
+    assert_eq!(changes[1].entries[255].red, 255);
+    assert_eq!(avi_parser.palette_changes(1).count(), 0);
+}
 
+#[test]
+fn test_avi_chapter_marks() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    assert!(avi_parser.chapter_marks().unwrap().is_empty());
+
+    // Append cue points at frames 10 and 2, and a label for the first
+    let mut data = TEST_AVI.to_vec();
+    data.extend_from_slice(b"cue \x34\0\0\0");
+    data.extend_from_slice(&2u32.to_le_bytes());
+    for (id, frame) in [(1u32, 10u32), (2, 2)] {
+        data.extend_from_slice(&id.to_le_bytes());
+        data.extend_from_slice(&frame.to_le_bytes());
+        data.extend_from_slice(b"data");
+        data.extend_from_slice(&[0; 12]);
+    }
+    data.extend_from_slice(b"LIST\x16\0\0\0adtllabl\x0a\0\0\0");
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(b"Intro\0");
+
+    let riff_size = (data.len() - 8) as u32;
+    data[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(data.clone()))).unwrap();
+    let mark = |time_us, name: &str| avi::ChapterMark {
+        time_us,
+        name: name.into(),
+    };
+    assert_eq!(
+        avi_parser.chapter_marks().unwrap(),
+        [mark(100_000, ""), mark(500_000, "Intro")]
+    );
+
+    // Truncating the cue chunk is an error rather than no marks
+    let cue = TEST_AVI.len() + 8;
+    data[cue..cue + 4].copy_from_slice(&3u32.to_le_bytes());
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+    assert!(avi_parser.chapter_marks().is_err());
+}
+
+#[test]
+fn test_avi_avix() {
+    let mut avih = [0u8; 56];
+    avih[24..28].copy_from_slice(&1u32.to_le_bytes());
+    let mut strh = [0u8; 56];
+    strh[0..4].copy_from_slice(b"vids");
+    let mut strf = [0u8; 40];
+    strf[0..4].copy_from_slice(&40u32.to_le_bytes());
+    let mut vprp = [0u8; 68];
+    vprp[20..24].copy_from_slice(&0x0010_0009u32.to_le_bytes());
+    vprp[32..36].copy_from_slice(&1u32.to_le_bytes());
+    vprp[36..40].copy_from_slice(&480u32.to_le_bytes());
+    let movi = |frames: usize| {
+        let chunks: Vec<_> = (0..frames)
+            .map(|_| build_chunk(b"00dc", b"frame"))
+            .collect();
+        build_list(b"LIST", b"movi", &chunks)
+    };
+
+    let mut data = build_list(
+        b"RIFF",
+        b"AVI ",
+        &[
+            build_list(
+                b"LIST",
+                b"hdrl",
+                &[
+                    build_chunk(b"avih", &avih),
+                    build_list(
+                        b"LIST",
+                        b"strl",
+                        &[
+                            build_chunk(b"strh", &strh),
+                            build_chunk(b"strf", &strf),
+                            build_chunk(b"strd", &[1, 2, 3]),
+                            build_chunk(b"strn", b"Camera 1\0"),
+                            build_chunk(b"vprp", &vprp),
+                        ],
+                    ),
+                ],
+            ),
+            movi(3),
+        ],
+    );
+    data.extend(build_list(b"RIFF", b"AVIX", &[movi(2)]));
+    data.extend(build_list(b"RIFF", b"AVIX", &[movi(4)]));
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(data))).unwrap();
     let avi::StreamInfo::Video(ref video_stream) = avi_parser.stream_info[0] else {
         panic!("stream 0 not video");
     };
-    let avi::StreamInfo::Audio(ref audio_stream) = avi_parser.stream_info[1] else {
-        panic!("stream 1 not audio");
+    assert_eq!(video_stream.strd.as_deref(), Some(&[1u8, 2, 3][..]));
+    assert_eq!(video_stream.strn.as_deref(), Some("Camera 1"));
+    let Some(ref video_properties) = video_stream.video_properties else {
+        panic!("vprp not parsed");
     };
-    let Some(stream) = avi_parser.find_best_stream::<avi::VideoStream>() else {
-        panic!("stream not found");
+    assert_eq!(
+        video_properties.frame_aspect_ratio,
+        avi::FrameAspectRatio { x: 16, y: 9 }
+    );
+    assert_eq!(video_properties.field_info.len(), 1);
+    assert_eq!(video_properties.field_info[0].compressed_bm_height, 480);
+    assert_eq!(avi_parser.extended_movi.len(), 2);
+    let stream_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    assert_eq!(
+        avi_parser.stream_chunks(stream_id, avi_parser.movi).count(),
+        3
+    );
+    assert_eq!(avi_parser.movi_chunks(stream_id).count(), 9);
+}
+
+#[test]
+fn test_avi_flags() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    let flags = avi_parser.avi_header.flags;
+    assert_ne!(flags & avi::AviMainHeader::FLAG_HAS_INDEX, 0);
+    assert_eq!(flags & avi::AviMainHeader::FLAG_MUST_USE_INDEX, 0);
+    let avi_header = &avi_parser.avi_header;
+    assert!(avi_header.has_index());
+    assert!(!avi_header.must_use_index());
+    assert!(avi_header.is_interleaved());
+    assert!(!avi_header.is_capture_file());
+    assert!(!avi_header.is_copyrighted());
+    let stream_header = avi_parser.stream_info[0].stream_header();
+    assert_eq!(stream_header.flags & avi::AviStreamHeader::FLAG_DISABLED, 0);
+
+    #[cfg(feature = "bitflags")]
+    assert_eq!(
+        avi_parser.avi_header.flags_decoded(),
+        avi::AviFlags::HAS_INDEX | avi::AviFlags::IS_INTERLEAVED | avi::AviFlags::TRUST_CK_TYPE
+    );
+}
+
+#[test]
+fn test_avi_pixel_format() {
+    use riffparse::fourcc::Fourcc;
+
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    assert_eq!(
+        avi_parser.pixel_format(),
+        Some(avi::PixelFormat::Compressed(Fourcc::new(*b"MJPG")))
+    );
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(MP3_AVI))).unwrap();
+    assert_eq!(avi_parser.pixel_format(), None);
+
+    let mut strf = [0u8; 52];
+    strf[0..4].copy_from_slice(&40u32.to_le_bytes());
+    strf[16..20].copy_from_slice(&avi::BitmapInfo::BI_BITFIELDS.to_le_bytes());
+    strf[40..44].copy_from_slice(&0xf800u32.to_le_bytes());
+    strf[44..48].copy_from_slice(&0x07e0u32.to_le_bytes());
+    strf[48..52].copy_from_slice(&0x001fu32.to_le_bytes());
+    let data = build_list(b"RIFF", b"AVI ", &[build_chunk(b"strf", &strf)]);
+    let parser = RiffParser::new(Cursor::new(data));
+    let riff = parser.riff().unwrap();
+    let Some(Ok(RiffType::Chunk(strf))) = parser.chunks(riff).next() else {
+        panic!("strf not found");
     };
-    assert_eq!(stream.stream_header.priority, 0);
-    assert_eq!(stream.stream_id, video_stream.stream_id);
+    let bitmap_info = parser.read_data_struct::<avi::BitmapInfo>(strf).unwrap();
+    assert_eq!(
+        bitmap_info.pixel_format(),
+        avi::PixelFormat::Bitfields {
+            red_mask: 0xf800,
+            green_mask: 0x07e0,
+            blue_mask: 0x001f
+        }
+    );
+    let yuy2 = avi::BitmapInfo {
+        compression: u32::from(Fourcc::new(*b"YUY2")),
+        bitfield_masks: None,
+        ..bitmap_info
+    };
+    assert_eq!(
+        yuy2.pixel_format(),
+        avi::PixelFormat::Yuv(Fourcc::new(*b"YUY2"))
+    );
+}
 
-    assert_eq!(avi_parser.movi_chunks(video_stream.stream_id).count(), 20);
-    assert_eq!(avi_parser.movi_chunks(audio_stream.stream_id).count(), 15);
+#[test]
+fn test_avi_display() {
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(TEST_AVI))).unwrap();
+    assert_eq!(
+        format!("{}", avi_parser.avi_header),
+        "Resolution: 32x24\nFrame rate: 20.00 fps\nFrames: 20\nDuration: 00:00:01\n\
+         Streams: 2\nMax bytes/sec: 57000"
+    );
+    assert_eq!(
+        format!("{}", avi_parser.stream_info[0].stream_header()),
+        "Type: vids\nCodec: MJPG\nRate: 20.00/s\nLength: 20\nDuration: 00:00:01"
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_avi_from_file() {
+    let avi_parser =
+        avi::AviParser::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test.avi")).unwrap();
+    assert_eq!(avi_parser.stream_count(), 2);
+    assert!(matches!(
+        avi::AviParser::from_file("does/not/exist.avi"),
+        Err(RiffError::Io(_))
+    ));
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_mmap_riff_parser() {
+    use riffparse::mmap::MmapRiffParser;
+
+    // SAFETY: test.avi is a checked in fixture nothing writes to
+    let parser = unsafe {
+        MmapRiffParser::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test.avi"))
+    }
+    .unwrap();
+    let riff = parser.riff().unwrap();
+    assert_eq!(riff.id(), avi::tag::AVI);
+    assert_eq!(parser.chunks(riff).count(), 5);
+
+    let avi_parser = avi::AviParser::new(parser.into_inner()).unwrap();
+    assert_eq!(avi_parser.stream_count(), 2);
+    assert!(matches!(
+        // SAFETY: the file doesn't exist, so nothing is mapped
+        unsafe { MmapRiffParser::from_file("does/not/exist.avi") },
+        Err(RiffError::Io(_))
+    ));
+}
+
+#[test]
+fn test_avi_rec() {
+    let mut avih = [0u8; 56];
+    avih[16..20].copy_from_slice(&3u32.to_le_bytes());
+    avih[24..28].copy_from_slice(&2u32.to_le_bytes());
+    let mut vids = [0u8; 56];
+    vids[0..4].copy_from_slice(b"vids");
+    let mut auds = [0u8; 56];
+    auds[0..4].copy_from_slice(b"auds");
+    let mut pcm = [0u8; 18];
+    pcm[0..2].copy_from_slice(&1u16.to_le_bytes());
+    let rec = || {
+        build_list(
+            b"LIST",
+            b"rec ",
+            &[build_chunk(b"00dc", b"frame"), build_chunk(b"01wb", b"pcm")],
+        )
+    };
+
+    let data = build_list(
+        b"RIFF",
+        b"AVI ",
+        &[
+            build_list(
+                b"LIST",
+                b"hdrl",
+                &[
+                    build_chunk(b"avih", &avih),
+                    build_list(
+                        b"LIST",
+                        b"strl",
+                        &[build_chunk(b"strh", &vids), build_chunk(b"strf", &[0; 40])],
+                    ),
+                    build_list(
+                        b"LIST",
+                        b"strl",
+                        &[build_chunk(b"strh", &auds), build_chunk(b"strf", &pcm)],
+                    ),
+                ],
+            ),
+            build_list(
+                b"LIST",
+                b"movi",
+                &[rec(), build_chunk(b"00dc", b"frame"), rec()],
+            ),
+        ],
+    );
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+    let video_id = avi::tag::stream(0, avi::tag::DATA_VIDEO_COMPRESSED);
+    let frames: Vec<_> = avi_parser
+        .movi_chunks(video_id)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(frames.len() as u32, avi_parser.true_total_frames());
+    let audio_id = avi::tag::stream(1, avi::tag::DATA_AUDIO);
+    assert_eq!(avi_parser.movi_chunks(audio_id).count(), 2);
+    let ids: Vec<_> = avi_parser
+        .iter_all_chunks()
+        .map(|result| result.unwrap().0)
+        .collect();
+    assert_eq!(ids, vec![video_id, audio_id, video_id, video_id, audio_id]);
+    assert_eq!(avi_parser.first_frame(video_id).unwrap(), b"frame");
+}
+
+#[test]
+fn test_avi_odml() {
+    let mut avih = [0u8; 56];
+    avih[16..20].copy_from_slice(&20u32.to_le_bytes());
+    let mut dmlh = [0u8; 248];
+    dmlh[0..4].copy_from_slice(&50000u32.to_le_bytes());
+    let data = build_list(
+        b"RIFF",
+        b"AVI ",
+        &[
+            build_list(
+                b"LIST",
+                b"hdrl",
+                &[
+                    build_chunk(b"avih", &avih),
+                    build_list(b"LIST", b"odml", &[build_chunk(b"dmlh", &dmlh)]),
+                ],
+            ),
+            build_list(b"LIST", b"movi", &[]),
+        ],
+    );
+    let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+    assert_eq!(avi_parser.avi_header.total_frames, 50000);
+    assert_eq!(avi_parser.true_total_frames(), 50000);
+}
+
+#[test]
+fn test_riff_errors() {
+    let parser = RiffParser::new(Cursor::new(build_list(b"LIST", b"AVI ", &[])));
+    assert!(matches!(parser.riff(), Err(RiffError::NotRiff)));
+
+    let mut data = build_list(b"RIFF", b"AVI ", &[build_chunk(b"avih", &[0; 8])]);
+    data.truncate(data.len() - 12);
+    let parser = RiffParser::new(Cursor::new(data));
+    let riff = parser.riff().unwrap();
+    assert!(matches!(
+        parser.chunks(riff).next(),
+        Some(Err(RiffError::MalformedChunk { position: 12, .. }))
+    ));
+    assert!(matches!(
+        parser.read_data(riff, &mut [0; 64]),
+        Err(RiffError::MalformedChunk { position: 12, .. })
+    ));
+
+    /// Fails every read from `fail_at` on, like a disk error rather than the end of the file
+    struct Failing {
+        inner: Cursor<Vec<u8>>,
+        fail_at: u64,
+    }
+
+    impl Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> riffparse::binrw::io::Result<usize> {
+            if self.inner.position() >= self.fail_at {
+                return Err(riffparse::binrw::io::ErrorKind::Other.into());
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for Failing {
+        fn seek(&mut self, pos: SeekFrom) -> riffparse::binrw::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    let data = build_list(b"RIFF", b"AVI ", &[build_chunk(b"avih", &[0; 8])]);
+    let parser = RiffParser::new(Failing {
+        inner: Cursor::new(data.clone()),
+        fail_at: 0,
+    });
+    assert!(matches!(parser.riff(), Err(RiffError::Io(_))));
+    let parser = RiffParser::new(Failing {
+        inner: Cursor::new(data),
+        fail_at: 12,
+    });
+    let riff = parser.riff().unwrap();
+    assert!(matches!(
+        parser.chunks(riff).next(),
+        Some(Err(RiffError::Io(_)))
+    ));
+}
+
+#[test]
+fn test_webp_lossy() {
+    let vp8 = [
+        0x50, 0x01, 0x00, 0x9d, 0x01, 0x2a, 0x20, 0x40, 0x18, 0x00, 0x00, 0x00,
+    ];
+    let data = build_list(b"RIFF", b"WEBP", &[build_chunk(b"VP8 ", &vp8)]);
+    let webp = webp::WebpParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+    assert_eq!(webp.width(), 32);
+    assert_eq!(webp.height(), 24);
+    assert!(!webp.has_alpha());
+    assert!(!webp.is_lossless());
+    assert!(!webp.is_extended());
+}
+
+#[test]
+fn test_webp_lossless() {
+    // 32x24, alpha_is_used
+    let bits: u32 = 31 | (23 << 14) | (1 << 28);
+    let mut vp8l = vec![0x2f];
+    vp8l.extend_from_slice(&bits.to_le_bytes());
+    let data = build_list(b"RIFF", b"WEBP", &[build_chunk(b"VP8L", &vp8l)]);
+    let webp = webp::WebpParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+    assert_eq!(webp.width(), 32);
+    assert_eq!(webp.height(), 24);
+    assert!(webp.has_alpha());
+    assert!(webp.is_lossless());
+    assert!(!webp.is_extended());
+}
+
+#[test]
+fn test_webp_extended() {
+    let vp8x = [0x2c, 0, 0, 0, 31, 0, 0, 23, 0, 0];
+    let bits: u32 = 31 | (23 << 14);
+    let mut vp8l = vec![0x2f];
+    vp8l.extend_from_slice(&bits.to_le_bytes());
+    let data = build_list(
+        b"RIFF",
+        b"WEBP",
+        &[
+            build_chunk(b"VP8X", &vp8x),
+            build_chunk(b"ICCP", b"icc"),
+            build_chunk(b"VP8L", &vp8l),
+            build_chunk(b"EXIF", b"exif"),
+        ],
+    );
+    let webp = webp::WebpParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+    assert_eq!(webp.width(), 32);
+    assert_eq!(webp.height(), 24);
+    assert!(!webp.has_alpha());
+    assert!(webp.is_lossless());
+    assert!(webp.is_extended());
+    let parser = webp.riff_parser();
+    assert_eq!(parser.read_data_vec(webp.iccp.unwrap()).unwrap(), b"icc");
+    assert_eq!(parser.read_data_vec(webp.exif.unwrap()).unwrap(), b"exif");
+    assert!(webp.xmp.is_none());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_fourcc_bit_ops() {
+    use riffparse::fourcc::Fourcc;
+
+    let id = avi::tag::stream(1, avi::tag::DATA_AUDIO);
+    let datatype = id & Fourcc::new(*b"\0\0\xff\xff");
+    let prefix = id & Fourcc::new(*b"\xff\xff\0\0");
+    assert_eq!(datatype, Fourcc::new(*b"\0\0wb"));
+    assert_eq!(prefix, Fourcc::new(*b"01\0\0"));
+    assert_eq!(prefix | datatype, id);
+    assert_eq!(
+        prefix | Fourcc::new(*b"\0\0dc"),
+        avi::tag::stream(1, avi::tag::DATA_VIDEO_COMPRESSED)
+    );
+}
+
+#[test]
+fn test_fourcc_stream_id() {
+    use riffparse::fourcc::Fourcc;
+
+    let id = avi::tag::stream(12, avi::tag::DATA_AUDIO);
+    assert!(id.matches_prefix(*b"12"));
+    assert!(!id.matches_prefix(*b"01"));
+    assert_eq!(id.stream_index(), Some(12));
+    assert_eq!(id.stream_type(), Some(avi::tag::DATA_AUDIO));
+    assert_eq!(Fourcc::new(*b"00dc").stream_index(), Some(0));
+    assert!(Fourcc::new(*b"ix00").matches_prefix(*b"ix"));
+    assert_eq!(Fourcc::new(*b"ix00").stream_index(), None);
+    assert_eq!(Fourcc::new(*b"0xdc").stream_type(), None);
+    assert_eq!(avi::tag::JUNK.stream_index(), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_fourcc_hash() {
+    use riffparse::fourcc::Fourcc;
+    use std::{
+        collections::HashMap,
+        hash::{BuildHasher, RandomState},
+    };
+
+    let state = RandomState::new();
+    assert_eq!(
+        state.hash_one(Fourcc::new(*b"movi")),
+        state.hash_one(Fourcc::from(u32::from_le_bytes(*b"movi")))
+    );
+
+    let mut map = HashMap::new();
+    map.insert(avi::tag::MOVI, 1usize);
+    map.insert(avi::tag::HDRL, 2usize);
+    assert_eq!(map.get(&Fourcc::new(*b"movi")), Some(&1));
+    assert_eq!(map.get(&Fourcc::new(*b"hdrl")), Some(&2));
+    assert_eq!(map.get(&Fourcc::new(*b"idx1")), None);
+}
+
+#[test]
+fn test_fourcc_default() {
+    use riffparse::fourcc::Fourcc;
+
+    assert_eq!(Fourcc::default(), Fourcc::from(0));
+    assert!(Fourcc::default().is_null());
+    assert!(!avi::tag::MOVI.is_null());
+}
+
+#[test]
+fn test_fourcc_into() {
+    use riffparse::fourcc::Fourcc;
+
+    let bytes: [u8; 4] = avi::tag::MOVI.into();
+    assert_eq!(&bytes, b"movi");
+    let value: u32 = avi::tag::MOVI.into();
+    assert_eq!(value, u32::from_le_bytes(*b"movi"));
+    assert_eq!(Fourcc::from(value), avi::tag::MOVI);
+}
+
+#[test]
+fn test_fourcc_map() {
+    use riffparse::fourcc::{FourccMap, SmallFourccMap};
+
+    let mut map = FourccMap::new();
+    assert_eq!(map.insert(tag::WAVE, 1), None);
+    assert_eq!(map.insert(tag::AVI, 2), None);
+    assert_eq!(map.insert(tag::WAVE, 3), Some(1));
+    assert!(map.contains_key(tag::AVI));
+    assert_eq!(map.get(tag::WAVE), Some(&3));
+    let keys: Vec<_> = map.iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, vec![tag::AVI, tag::WAVE]);
+    assert_eq!(map.remove(tag::AVI), Some(2));
+    assert_eq!(map.len(), 1);
+
+    let mut small = SmallFourccMap::<u32, 2>::new();
+    assert_eq!(small.insert(tag::RIFF, 1), Ok(None));
+    assert_eq!(small.insert(tag::LIST, 2), Ok(None));
+    assert_eq!(small.insert(tag::RIFF, 3), Ok(Some(1)));
+    assert_eq!(small.insert(tag::JUNK, 4), Err((tag::JUNK, 4)));
+    assert_eq!(small.remove(tag::LIST), Some(2));
+    assert_eq!(small.insert(tag::JUNK, 4), Ok(None));
+    assert_eq!(small.get(tag::JUNK), Some(&4));
+    assert_eq!(small.len(), 2);
+}
+
+#[test]
+fn test_tag() {
+    assert_eq!(tag::MOVI, avi::tag::MOVI);
+    assert_eq!(tag::DATA, smf::tag::DATA);
+    assert_eq!(tag::FMT.bytes(), *b"fmt ");
+    assert_eq!(tag::CUE.bytes(), *b"cue ");
+}
+
+#[test]
+fn test_fourcc_ord() {
+    use alloc::collections::{BTreeMap, BTreeSet};
+    use riffparse::fourcc::Fourcc;
+
+    assert!(Fourcc::new(*b"AVI ") < Fourcc::new(*b"WAVE"));
+    // Numerically (little endian) "00dc" > "01wb" since 'c' > 'b' is the high byte
+    assert!(Fourcc::new(*b"00dc") < Fourcc::new(*b"01wb"));
+    assert!(Fourcc::new(*b"01dc") > Fourcc::new(*b"00wb"));
+
+    let set: BTreeSet<Fourcc> = [
+        avi::tag::STRL,
+        avi::tag::AVI,
+        avi::tag::MOVI,
+        avi::tag::HDRL,
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(
+        set.into_iter().collect::<Vec<_>>(),
+        [
+            avi::tag::AVI,
+            avi::tag::HDRL,
+            avi::tag::MOVI,
+            avi::tag::STRL
+        ]
+    );
+
+    let mut map = BTreeMap::new();
+    map.insert(Fourcc::new(*b"WAVE"), 2);
+    map.insert(Fourcc::new(*b"AVI "), 1);
+    map.insert(Fourcc::new(*b"RMID"), 3);
+    assert_eq!(map.values().copied().collect::<Vec<_>>(), [1, 3, 2]);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_parser() {
+    use riffparse::async_io::AsyncRiffParser;
+
+    let sync_parser = RiffParser::new(Cursor::new(TEST_AVI));
+    let parser = AsyncRiffParser::new(std::io::Cursor::new(TEST_AVI));
+    let riff = parser.riff().await.unwrap();
+    assert_eq!(riff.id(), avi::tag::AVI);
+
+    let mut sync_chunks = sync_parser.chunks(sync_parser.riff().unwrap());
+    let mut chunks = parser.chunks(riff);
+    while let Some(item) = chunks.next_chunk().await {
+        let item = item.unwrap();
+        let sync_item = sync_chunks.next().unwrap().unwrap();
+        assert_eq!(item.id(), sync_item.id());
+        if let (RiffType::Chunk(chunk), RiffType::Chunk(sync_chunk)) = (item, sync_item) {
+            assert_eq!(
+                parser.read_data_vec(chunk).await.unwrap(),
+                sync_parser.read_data_vec(sync_chunk).unwrap()
+            );
+        }
+    }
+    assert!(sync_chunks.next().is_none());
+
+    let mut rifx = b"RIFX\0\0\0\x1eWAVE".to_vec();
+    rifx.extend_from_slice(b"data\0\0\0\x04\0\0\x01\x02");
+    rifx.extend_from_slice(b"LIST\0\0\0\x06INFOab");
+    let parser = AsyncRiffParser::new(std::io::Cursor::new(rifx));
+    let riff = parser.riff().await.unwrap();
+    assert_eq!(parser.endian(), riffparse::binrw::Endian::Big);
+    assert_eq!(riff.data_size(), 26);
+    let mut chunks = parser.chunks(riff);
+    let Some(Ok(RiffType::Chunk(data))) = chunks.next_chunk().await else {
+        panic!("expected data chunk");
+    };
+    assert_eq!(parser.read_data_struct::<u32>(data).await.unwrap(), 0x0102);
+    let Some(Ok(RiffType::List(info))) = chunks.next_chunk().await else {
+        panic!("expected INFO list");
+    };
+    assert_eq!(info.data_size(), 2);
+    assert!(chunks.next_chunk().await.is_none());
+}
+
+#[cfg(feature = "serde")]
+fn assert_serde_roundtrip<T: Debug + serde::Serialize + serde::de::DeserializeOwned>(value: &T) {
+    let json = serde_json::to_string(value).unwrap();
+    let decoded: T = serde_json::from_str(&json).unwrap();
+    assert_eq!(alloc::format!("{value:?}"), alloc::format!("{decoded:?}"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    use riffparse::fourcc::Fourcc;
+
+    for data in [TEST_AVI, MP3_AVI] {
+        let avi_parser = avi::AviParser::new(RiffParser::new(Cursor::new(data))).unwrap();
+        assert_serde_roundtrip(&avi_parser.avi_header);
+        for stream in &avi_parser.stream_info {
+            assert_serde_roundtrip(stream);
+        }
+    }
+
+    let format = avi::WaveFormatEx {
+        channels: 2,
+        samples_per_sec: 44100,
+        av_bytes_per_sec: 176400,
+        block_align: 4,
+        bits_per_sample: 16,
+        size: 22,
+    };
+    assert_serde_roundtrip(&avi::WaveFormat::Extensible(avi::WaveFormatExtensible {
+        format: format.clone(),
+        samples: 16,
+        channel_mask: 3,
+        sub_format: avi::Guid {
+            data1: 1,
+            data2: 0,
+            data3: 0x10,
+            data4: [0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71],
+        },
+    }));
+    assert_serde_roundtrip(&avi::WaveFormat::Mpeg1(avi::Mpeg1WaveFormat {
+        format,
+        head_layer: 2,
+        head_bitrate: 128000,
+        head_mode: 1,
+        head_mode_ext: 0,
+        head_emphasis: 1,
+        head_flags: 0x18,
+        pts_low: 0,
+        pts_high: 0,
+    }));
+
+    assert_eq!(serde_json::to_string(&avi::tag::MOVI).unwrap(), "\"movi\"");
+    assert_eq!(
+        serde_json::to_string(&Fourcc::new([0xff, 0, 0, 0])).unwrap(),
+        "255"
+    );
+    assert_serde_roundtrip(&Fourcc::new([0xff, 0, 0, 0]));
+    assert!(serde_json::from_str::<Fourcc>("\"movie\"").is_err());
 }
 
 #[cfg(feature = "embedded-io")]
@@ -205,6 +3110,21 @@ fn test_avi_embeddedio() {
     assert_eq!(TEST_AVI_SNAPSHOT, String::from_utf8(output).unwrap());
 }
 
+#[cfg(feature = "embedded-io")]
+#[test]
+fn test_embedded_adapter_inner() {
+    let mut adapter = EmbeddedAdapter(embedded::Reader::new(vec![1, 2, 3]));
+    let mut byte = [0u8; 1];
+    embedded_io::Read::read(adapter.inner_mut(), &mut byte).unwrap();
+    assert_eq!(byte, [1]);
+    adapter.read_exact(&mut byte).unwrap();
+    assert_eq!(byte, [2]);
+    assert!(format!("{:?}", adapter.inner()).contains("pos: 2"));
+    let mut reader = adapter.into_inner();
+    embedded_io::Read::read(&mut reader, &mut byte).unwrap();
+    assert_eq!(byte, [3]);
+}
+
 #[cfg(feature = "std")]
 fn write_snapshot(avi: &[u8], snapshot_file: &str) {
     use std::{fs::File, path::PathBuf};
@@ -226,3 +3146,10 @@ fn test_test_avi_snapshot() {
 fn test_mp3_avi_snapshot() {
     write_snapshot(MP3_AVI, "tests/mp3.avi.snapshot");
 }
+
+#[cfg(feature = "std")]
+#[test]
+#[ignore]
+fn test_rmid_snapshot() {
+    write_snapshot(&build_rmid(), "tests/rmid.snapshot");
+}